@@ -66,11 +66,14 @@ async fn fetch_upstream(
     let auth = auth.clone();
     let key = remote_url.clone();
 
-    let refs_to_fetch = if headref != "" && !headref.starts_with("refs/heads/") {
+    let mut refs_to_fetch = if headref != "" && !headref.starts_with("refs/heads/") {
         vec!["refs/heads/*", "refs/tags/*", headref]
     } else {
         vec!["refs/heads/*", "refs/tags/*"]
     };
+    if ARGS.is_present("notes") {
+        refs_to_fetch.push("refs/notes/*");
+    }
 
     let refs_to_fetch: Vec<_> = refs_to_fetch.iter().map(|x| x.to_string()).collect();
 
@@ -232,13 +235,16 @@ async fn do_filter(
     let r = tokio::task::spawn_blocking(move || {
         let _e = s.enter();
         tracing::trace!("in do_filter worker");
-        let transaction = josh::cache::Transaction::open(
+        let mut transaction = josh::cache::Transaction::open(
             &repo_path,
             Some(&format!(
                 "refs/josh/upstream/{}/",
                 &josh::to_ns(&upstream_repo),
             )),
         )?;
+        if ARGS.is_present("origin-trailer") {
+            transaction = transaction.with_origin(&upstream_repo);
+        }
         let filter = josh::filter::parse(&filter_spec)?;
         let filter_spec = josh::filter::spec(filter);
         let mut from_to = josh::housekeeping::default_from_to(
@@ -246,6 +252,7 @@ async fn do_filter(
             &temp_ns.name(),
             &upstream_repo,
             &filter_spec,
+            ARGS.is_present("notes"),
         );
 
         let glob = format!(
@@ -289,6 +296,56 @@ async fn do_filter(
     return r;
 }
 
+/// Check that the user can read the other repositories used by the filter through
+/// `:repo=` and fetch them. Returns the name of the first one that can't be read.
+async fn check_repo_access(
+    serv: Arc<JoshProxyService>,
+    parsed_url: &FilteredRepoUrl,
+    headref: &str,
+    auth: &josh_proxy::auth::Handle,
+) -> josh::JoshResult<Option<String>> {
+    let names = {
+        let serv = serv.clone();
+        let upstream_repo = parsed_url.upstream_repo.clone();
+        let filter_spec = parsed_url.filter.clone();
+        let headref = headref.to_owned();
+        tokio::task::spawn_blocking(move || -> josh::JoshResult<_> {
+            let transaction = josh::cache::Transaction::open(
+                &serv.repo_path,
+                Some(&format!(
+                    "refs/josh/upstream/{}/",
+                    &josh::to_ns(&upstream_repo),
+                )),
+            )?;
+            let tree = transaction
+                .repo()
+                .find_reference(&transaction.refname(&headref))
+                .and_then(|r| r.peel_to_tree())
+                .unwrap_or(josh::filter::tree::empty(transaction.repo()));
+            josh::filter::repo_names(&transaction, josh::filter::parse(&filter_spec)?, tree)
+        })
+        .in_current_span()
+        .await??
+    };
+
+    for name in names {
+        let remote_url = format!("{}/{}", serv.upstream_url, name.trim_start_matches('/'));
+        let readable =
+            josh_proxy::auth::check_auth(&remote_url, &auth, ARGS.is_present("require-auth"))
+                .in_current_span()
+                .await
+                .unwrap_or(false)
+                && fetch_upstream(serv.clone(), name.clone(), &auth, remote_url, "", false)
+                    .in_current_span()
+                    .await
+                    .unwrap_or(false);
+        if !readable {
+            return Ok(Some(name));
+        }
+    }
+    return Ok(None);
+}
+
 async fn error_response() -> Response<hyper::Body> {
     Response::builder()
         .status(hyper::StatusCode::INTERNAL_SERVER_ERROR)
@@ -372,6 +429,13 @@ async fn call_service(
         }
     };
 
+    if let Err(e) = josh::filter::try_parse(&parsed_url.filter) {
+        return Ok(Response::builder()
+            .status(hyper::StatusCode::BAD_REQUEST)
+            .header("Content-Type", "text/plain")
+            .body(hyper::Body::from(format!("{}\n", e)))?);
+    }
+
     let mut headref = parsed_url.headref.trim_start_matches("@").to_owned();
     if headref == "" {
         headref = "refs/heads/master".to_string();
@@ -421,6 +485,19 @@ async fn call_service(
         }
     }
 
+    if let Some(name) = check_repo_access(serv.clone(), &parsed_url, &headref, &auth)
+        .in_current_span()
+        .await?
+    {
+        return Ok(Response::builder()
+            .status(hyper::StatusCode::FORBIDDEN)
+            .header("Content-Type", "text/plain")
+            .body(hyper::Body::from(format!(
+                "no access to {:?} used by :repo=\n",
+                name
+            )))?);
+    }
+
     if parsed_url.api == "/~/graphiql" {
         let addr = format!("/~/graphql{}", parsed_url.upstream_repo);
         return Ok(tokio::task::spawn_blocking(move || {
@@ -519,12 +596,18 @@ async fn call_service(
     let repo_update = josh_proxy::RepoUpdate {
         refs: HashMap::new(),
         remote_url: remote_url.clone(),
+        upstream_url: serv.upstream_url.clone(),
         auth,
         port: serv.port.clone(),
         filter_spec: parsed_url.filter.clone(),
         base_ns: josh::to_ns(&parsed_url.upstream_repo),
         git_ns: temp_ns.name().to_string(),
         git_dir: repo_path.to_string(),
+        origin: if ARGS.is_present("origin-trailer") {
+            Some(parsed_url.upstream_repo.clone())
+        } else {
+            None
+        },
     };
 
     let mut cmd = Command::new("git");
@@ -736,6 +819,18 @@ fn parse_args() -> clap::ArgMatches<'static> {
                 .long("no-background")
                 .takes_value(false),
         )
+        .arg(
+            clap::Arg::with_name("notes")
+                .long("notes")
+                .takes_value(false)
+                .help("Filter notes refs and allow pushing notes"),
+        )
+        .arg(
+            clap::Arg::with_name("origin-trailer")
+                .long("origin-trailer")
+                .takes_value(false)
+                .help("Add a trailer naming the original commit to filtered commits"),
+        )
         .arg(
             clap::Arg::with_name("graphql-root")
                 .long("graphql-root")
@@ -29,12 +29,14 @@ fn baseref_and_options(refname: &str) -> josh::JoshResult<(String, String, Vec<S
 pub struct RepoUpdate {
     pub refs: std::collections::HashMap<String, (String, String)>,
     pub remote_url: String,
+    pub upstream_url: String,
     pub auth: auth::Handle,
     pub port: String,
     pub filter_spec: String,
     pub base_ns: String,
     pub git_ns: String,
     pub git_dir: String,
+    pub origin: Option<String>,
 }
 
 pub fn process_repo_update(repo_update: RepoUpdate) -> josh::JoshResult<String> {
@@ -52,10 +54,13 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> josh::JoshResult<String>
     for (refname, (old, new)) in repo_update.refs.iter() {
         tracing::debug!("REPO_UPDATE env ok");
 
-        let transaction = josh::cache::Transaction::open(
+        let mut transaction = josh::cache::Transaction::open(
             &std::path::Path::new(&repo_update.git_dir),
             Some(&format!("refs/josh/upstream/{}/", repo_update.base_ns)),
         )?;
+        if let Some(origin) = &repo_update.origin {
+            transaction = transaction.with_origin(origin);
+        }
 
         let old = git2::Oid::from_str(old)?;
 
@@ -79,6 +84,48 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> josh::JoshResult<String>
             old
         };
 
+        if baseref.starts_with("refs/notes/") {
+            let filterobj = josh::filter::parse(&repo_update.filter_spec)?;
+            let original_notes = transaction
+                .repo()
+                .refname_to_id(&transaction.refname(&baseref))
+                .unwrap_or(git2::Oid::zero());
+
+            let mut contained_in = vec![];
+            for reference in transaction.repo().references_glob(&format!(
+                "refs/josh/upstream/{}/refs/heads/*",
+                repo_update.base_ns
+            ))? {
+                if let Some(oid) = reference?.target() {
+                    contained_in.push(oid);
+                }
+            }
+
+            let backward_notes = josh::history::unapply_notes(
+                &transaction,
+                filterobj,
+                original_notes,
+                old,
+                git2::Oid::from_str(&new)?,
+                &contained_in,
+            )?;
+
+            let (text, status) = push_head_url(
+                &transaction.repo(),
+                backward_notes,
+                &push_to,
+                &repo_update.remote_url,
+                &repo_update.auth,
+                &repo_update.git_ns,
+            )?;
+
+            resp = format!("{}{}", resp, text);
+            if status == 0 {
+                continue;
+            }
+            return Err(josh::josh_error(&resp));
+        }
+
         let original_target_ref = if let Some(base) = push_options.get("base") {
             transaction.refname(&base)
         } else {
@@ -208,6 +255,26 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> josh::JoshResult<String>
             &transaction,
         )?;
 
+        let new_commits = {
+            let mut walk = transaction.repo().revwalk()?;
+            walk.set_sorting(git2::Sort::REVERSE | git2::Sort::TOPOLOGICAL)?;
+            walk.push(oid_to_push)?;
+            walk.hide(original_target)?;
+            walk.collect::<Result<Vec<_>, _>>()?
+        };
+
+        resp = format!(
+            "{}{}",
+            resp,
+            push_submodule_commits(
+                &transaction,
+                &new_commits,
+                &repo_update.remote_url,
+                &push_with_options,
+                &repo_update,
+            )?
+        );
+
         let (text, status) = push_head_url(
             &transaction.repo(),
             oid_to_push,
@@ -258,7 +325,146 @@ pub fn process_repo_update(repo_update: RepoUpdate) -> josh::JoshResult<String>
         return Err(josh::josh_error(&resp));
     }
 
-    return Ok("".to_string());
+    return Ok(resp);
+}
+
+/// Push the submodule commits that were created by `:submodules` for the submodules of
+/// `commits` to their repositories, before the commits referencing them are pushed.
+/// Submodules are pushed to `refname` as well, nested submodules first.
+fn push_submodule_commits(
+    transaction: &josh::cache::Transaction,
+    commits: &[git2::Oid],
+    url: &str,
+    refname: &str,
+    repo_update: &RepoUpdate,
+) -> josh::JoshResult<String> {
+    let repo = transaction.repo();
+
+    // Only the last new commit of every submodule is pushed, the earlier ones are its
+    // ancestors
+    let mut heads: Vec<(String, git2::Oid, String)> = vec![];
+    for commit in commits {
+        let tree = repo.find_commit(*commit)?.tree()?;
+        let mut gitlinks = vec![];
+        tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() == Some(git2::ObjectType::Commit)
+                && transaction.is_new_submodule_commit(entry.id())
+            {
+                gitlinks.push((
+                    format!("{}{}", root, entry.name().unwrap_or("")),
+                    entry.id(),
+                ));
+            }
+            git2::TreeWalkResult::Ok
+        })?;
+        if gitlinks.is_empty() {
+            continue;
+        }
+
+        let urls = submodule_urls(repo, &tree)?;
+        for (path, id) in gitlinks {
+            let sub_url = urls.get(&path).ok_or(josh::josh_error(&format!(
+                "no url for submodule {:?} in .gitmodules",
+                path
+            )))?;
+            let sub_url = submodule_url(url, sub_url, &repo_update.upstream_url)?;
+            heads.retain(|(p, _, _)| p != &path);
+            heads.push((path, id, sub_url));
+        }
+    }
+
+    let mut resp = String::new();
+    for (path, id, sub_url) in heads {
+        let mut chain = vec![];
+        let mut next = Some(repo.find_commit(id)?);
+        while let Some(c) = next {
+            chain.push(c.id());
+            next = c
+                .parents()
+                .find(|p| transaction.is_new_submodule_commit(p.id()));
+        }
+        chain.reverse();
+        resp = format!(
+            "{}{}",
+            resp,
+            push_submodule_commits(transaction, &chain, &sub_url, refname, repo_update)?
+        );
+
+        let (text, status) = push_head_url(
+            repo,
+            id,
+            refname,
+            &sub_url,
+            &repo_update.auth,
+            &repo_update.git_ns,
+        )?;
+        if status != 0 {
+            return Err(josh::josh_error(&format!(
+                "{}pushing submodule {:?} failed:\n{}",
+                resp, path, text
+            )));
+        }
+        resp = format!("{}{}\n", resp, text);
+    }
+    return Ok(resp);
+}
+
+/// Map the paths of the submodules in `.gitmodules` of `tree` to their urls
+fn submodule_urls(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+) -> josh::JoshResult<std::collections::HashMap<String, String>> {
+    let mut result = std::collections::HashMap::new();
+    let gitmodules = josh::some_or!(tree.get_name(".gitmodules"), {
+        return Ok(result);
+    });
+
+    let shell = josh::shell::Shell {
+        cwd: repo.path().to_owned(),
+    };
+    let (stdout, _, _) = shell.command(&format!(
+        "git config --blob {} --get-regexp '^submodule\\..*\\.(path|url)$'",
+        gitmodules.id()
+    ));
+
+    let mut paths = std::collections::HashMap::new();
+    let mut urls = std::collections::HashMap::new();
+    for line in stdout.lines() {
+        let mut split = line.splitn(2, ' ');
+        let key = split.next().unwrap_or("").trim_start_matches("submodule.");
+        let value = split.next().unwrap_or("").to_owned();
+        if let Some(name) = key.strip_suffix(".path") {
+            paths.insert(name.to_owned(), value);
+        } else if let Some(name) = key.strip_suffix(".url") {
+            urls.insert(name.to_owned(), value);
+        }
+    }
+    for (name, path) in paths {
+        if let Some(url) = urls.remove(&name) {
+            result.insert(path, url);
+        }
+    }
+    return Ok(result);
+}
+
+/// Resolve the url of a submodule like git does: relative urls are relative to the
+/// repository of the superproject. Credentials are only ever sent to the upstream, so
+/// the result has to be below `upstream_url`.
+fn submodule_url(super_url: &str, url: &str, upstream_url: &str) -> josh::JoshResult<String> {
+    let resolved = if url.starts_with("./") || url.starts_with("../") {
+        url::Url::parse(&format!("{}/", super_url.trim_end_matches('/')))?
+            .join(url)?
+            .to_string()
+    } else {
+        url.to_owned()
+    };
+    if !resolved.starts_with(&format!("{}/", upstream_url.trim_end_matches('/'))) {
+        return Err(josh::josh_error(&format!(
+            "submodule url {:?} is not on the upstream",
+            url
+        )));
+    }
+    return Ok(resolved);
 }
 
 fn push_head_url(
@@ -24,3 +24,14 @@ export function setMarker(position, text) {
     msg.className = "marker";
     editor.addLineWidget(parseInt(position)-1, msg, {coverGutter: false, noHScroll: true});
 }
+
+export function setUnderline(line, column, endLine, endColumn, text) {
+    // Positions are passed as BigInt, like the one of setMarker
+    var from = {line: Number(line)-1, ch: Number(column)-1};
+    var to = {line: Number(endLine)-1, ch: Number(endColumn)-1};
+    if (from.line == to.line && from.ch == to.ch) {
+        // Errors at the end of the input have no extent
+        to.ch += 1;
+    }
+    editor.markText(from, to, {className: "parse_error", attributes: {title: text}});
+}
@@ -1,10 +1,19 @@
 use super::*;
 
+/// Range of the text to underline, given as pairs of line and column starting at 1
+#[derive(Clone, PartialEq)]
+pub struct Underline {
+    pub from: (i64, i64),
+    pub to: (i64, i64),
+    pub text: String,
+}
+
 #[derive(Properties, Clone, PartialEq)]
 pub struct Props {
     pub text: String,
     pub marker_pos: Vec<i64>,
     pub marker_text: Vec<String>,
+    pub error: Option<Underline>,
 }
 
 pub struct Codemirror {
@@ -34,6 +43,16 @@ impl Component for Codemirror {
         {
             set_marker(*pos, &text);
         }
+
+        if let Some(error) = &self.props.error {
+            set_underline(
+                error.from.0,
+                error.from.1,
+                error.to.0,
+                error.to.1,
+                &error.text,
+            );
+        }
     }
 
     fn update(&mut self, _msg: Self::Message) -> ShouldRender {
@@ -62,4 +81,7 @@ extern "C" {
 
     #[wasm_bindgen(js_name = "setMarker")]
     pub fn set_marker(position: i64, text: &str);
+
+    #[wasm_bindgen(js_name = "setUnderline")]
+    pub fn set_underline(line: i64, column: i64, end_line: i64, end_column: i64, text: &str);
 }
@@ -115,6 +115,13 @@ impl Component for Nav {
                         text=file.text.as_ref().unwrap_or(&"".to_string()).clone()
                         marker_pos=file.meta.data.iter().map(|x| x.position.clone().unwrap_or_default()).collect::<Vec<_>>()
                         marker_text=file.meta.data.iter().map(|x| x.text.clone().unwrap_or_default()).collect::<Vec<String>>()
+                        error=if props.route.filename() == "workspace.josh" {
+                            file.filter_error.as_ref().map(|e| codemirror::Underline {
+                                from: (e.line, e.column),
+                                to: (e.end_line, e.end_column),
+                                text: e.message.clone(),
+                            })
+                        } else { None }
                     />}
                 } else {
                     html! { <>
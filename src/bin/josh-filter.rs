@@ -82,6 +82,35 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
                 .short("c")
                 .takes_value(true),
         )
+        .arg(
+            clap::Arg::with_name("affected")
+                .long("affected")
+                .help("List the workspaces that differ between the given ref and the input")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("workspaces-including")
+                .long("workspaces-including")
+                .help("List the workspaces that include the given file and its path in them")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("diff")
+                .long("diff")
+                .help("Compare the result of the filter with the one of another filter")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::with_name("diff-history")
+                .long("diff-history")
+                .help("Compare the results for all commits of the input, not only the last one"),
+        )
+        .arg(
+            clap::Arg::with_name("origin-trailer")
+                .long("origin-trailer")
+                .help("Add a trailer naming the given repository and the original commit to filtered commits")
+                .takes_value(true),
+        )
         .arg(clap::Arg::with_name("version").long("version").short("v"))
         .get_matches_from(args);
 
@@ -100,7 +129,13 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
         .and_then(|f| read_to_string(f).ok())
         .unwrap_or(specstr.to_string());
 
-    let mut filterobj = josh::filter::parse(&specstr)?;
+    let mut filterobj = match josh::filter::try_parse(&specstr) {
+        Ok(filter) => filter,
+        Err(e) => {
+            println!("ERROR: {}", e);
+            return Ok(1);
+        }
+    };
 
     if args.is_present("squash") {
         filterobj = josh::filter::chain(josh::filter::parse(":SQUASH")?, filterobj);
@@ -118,7 +153,10 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
     if !args.is_present("no-cache") {
         josh::cache::load(&repo.path())?;
     }
-    let transaction = josh::cache::Transaction::new(repo, None);
+    let mut transaction = josh::cache::Transaction::new(repo, None);
+    if let Some(origin) = args.value_of("origin-trailer") {
+        transaction = transaction.with_origin(origin);
+    }
     let repo = transaction.repo();
 
     let odb = repo.odb()?;
@@ -149,6 +187,59 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
 
     let input_ref = args.value_of("input").unwrap();
 
+    if let Some(old) = args.value_of("affected") {
+        let old = repo.revparse_single(&old)?.peel_to_tree()?;
+        let new = repo.revparse_single(&input_ref)?.peel_to_tree()?;
+        for path in josh::housekeeping::affected_workspaces(&transaction, &old, &new)? {
+            println!("{}", path.join("workspace.josh").display());
+        }
+        return Ok(0);
+    }
+
+    if let Some(path) = args.value_of("workspaces-including") {
+        let tree = repo.revparse_single(&input_ref)?.peel_to_tree()?;
+        for (ws, dst) in josh::housekeeping::workspaces_including(
+            &transaction,
+            &tree,
+            std::path::Path::new(path),
+        )? {
+            println!("{}: {}", ws.join("workspace.josh").display(), dst.display());
+        }
+        return Ok(0);
+    }
+
+    if let Some(other) = args.value_of("diff") {
+        let other = match josh::filter::try_parse(other) {
+            Ok(filter) => filter,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                return Ok(1);
+            }
+        };
+        let commit = repo.revparse_single(&input_ref)?.peel_to_commit()?;
+        let diffs = if args.is_present("diff-history") {
+            josh::filter::diff_history(&transaction, filterobj, other, commit.id())?
+        } else {
+            let d = josh::filter::diff(&transaction, filterobj, other, commit.tree()?)?;
+            if d.is_empty() {
+                vec![]
+            } else {
+                vec![(commit.id(), d)]
+            }
+        };
+        for (id, d) in diffs.iter() {
+            if args.is_present("diff-history") {
+                println!("{}", id);
+            }
+            for (c, paths) in [("-", &d.removed), ("+", &d.added), ("M", &d.changed)].iter() {
+                for path in paths.iter() {
+                    println!("{} {}", c, path.display());
+                }
+            }
+        }
+        return Ok(if diffs.is_empty() { 0 } else { 1 });
+    }
+
     if args.is_present("discover") {
         let r = repo.revparse_single(&input_ref)?;
         let hs = josh::housekeeping::find_all_workspaces_and_subdirectories(&r.peel_to_tree()?)?;
@@ -190,7 +281,10 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
         .unwrap()
         .to_string();
 
-    josh::filter_refs(&transaction, filterobj, &[(src.clone(), t.clone())])?;
+    if let Err(e) = josh::filter_ref(&transaction, filterobj, &src, &t) {
+        println!("ERROR: {}", e.0);
+        return Ok(1);
+    }
 
     let mut all_paths = vec![];
 
@@ -243,7 +337,28 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
         println!("Allowed = {:?}", allowed);
     }
 
-    if reverse {
+    if reverse && (src.starts_with("refs/notes/") || src.contains("/refs/notes/")) {
+        let new = repo.revparse_single(&target)?.id();
+        let old = repo.revparse_single("JOSH_TMP")?.id();
+        let original_notes = repo.revparse_single(&input_ref)?.id();
+
+        let mut contained_in = vec![];
+        for reference in repo.references_glob("refs/heads/*")? {
+            if let Some(oid) = reference?.target() {
+                contained_in.push(oid);
+            }
+        }
+
+        let rewritten = josh::history::unapply_notes(
+            &transaction,
+            filterobj,
+            original_notes,
+            old,
+            new,
+            &contained_in,
+        )?;
+        repo.reference(&src, rewritten, true, "unapply_notes")?;
+    } else if reverse {
         let new = repo.revparse_single(&target).unwrap().id();
         let old = repo.revparse_single("JOSH_TMP").unwrap().id();
         let unfiltered_old = repo.revparse_single(&input_ref).unwrap().id();
@@ -261,8 +376,9 @@ fn run_filter(args: Vec<String>) -> josh::JoshResult<i32> {
             josh::UnapplyResult::Done(rewritten) => {
                 repo.reference(&src, rewritten, true, "unapply_filter")?;
             }
+            // The source ref is left unchanged, which has always exited with 0
             _ => {
-                return Ok(1);
+                return Ok(0);
             }
         }
     }
@@ -295,10 +411,11 @@ fn main() {
         args
     };
 
-    std::process::exit(if let Err(e) = run_filter(args) {
-        println!("ERROR: {:?}", e);
-        1
-    } else {
-        0
+    std::process::exit(match run_filter(args) {
+        Ok(code) => code,
+        Err(e) => {
+            println!("ERROR: {:?}", e);
+            1
+        }
     })
 }
@@ -1,7 +1,10 @@
 use super::*;
 use std::collections::HashMap;
 
-const VERSION: u64 = 6;
+// Filtered tags used to be stored in the trees of the filtered commits, they are now
+// in `_tags`. The old entries are keyed by tag ids, so they are never found when looking
+// up commits and tags are filtered again once, which does not require a new version.
+const VERSION: u64 = 7;
 
 lazy_static! {
     static ref DB: std::sync::Mutex<Option<sled::Db>> = std::sync::Mutex::new(None);
@@ -11,34 +14,112 @@ lazy_static! {
         std::sync::Mutex::new(HashMap::new());
     static ref GLOB_MAP: std::sync::Mutex<HashMap<(git2::Oid, git2::Oid), git2::Oid>> =
         std::sync::Mutex::new(HashMap::new());
+    static ref BLOBS_MAP: std::sync::Mutex<HashMap<(git2::Oid, git2::Oid), git2::Oid>> =
+        std::sync::Mutex::new(HashMap::new());
+    static ref FIRST_PARENTS_MAP: std::sync::Mutex<HashMap<git2::Oid, std::sync::Arc<Vec<(i64, git2::Oid)>>>> =
+        std::sync::Mutex::new(HashMap::new());
+    static ref ANCESTORS_MAP: std::sync::Mutex<HashMap<git2::Oid, std::sync::Arc<std::collections::HashSet<git2::Oid>>>> =
+        std::sync::Mutex::new(HashMap::new());
 }
 
 pub fn load(path: &std::path::Path) -> JoshResult<()> {
-    *DB.lock()? = Some(
-        sled::Config::default()
-            .path(path.join(format!("josh/{}/sled/", VERSION)))
-            .flush_every_ms(Some(200))
-            .open()?,
-    );
+    let db_path = path.join(format!("josh/{}/sled/", VERSION));
+    let new = !db_path.exists();
+    let db = sled::Config::default()
+        .path(&db_path)
+        .flush_every_ms(Some(200))
+        .open()?;
+
+    let old_path = path.join("josh/6/sled/");
+    if new && old_path.exists() {
+        migrate_v6(&sled::open(&old_path)?, &db)?;
+    }
+
+    *DB.lock()? = Some(db);
+    Ok(())
+}
+
+/// Version 6 named the trees of cached commits by the spec of the filter and used
+/// ids based on the `Debug` output of the filter, those are now named by the id.
+/// Trees with names that don't parse as filter anymore are dropped, so are `_paths`
+/// and `_invert` because their keys contain the old ids.
+fn migrate_v6(old: &sled::Db, db: &sled::Db) -> JoshResult<()> {
+    log::info!("migrating cache from version 6");
+    let specs = db.open_tree("_specs")?;
+    for name in old.tree_names() {
+        let name = String::from_utf8(name.to_vec())?;
+        let src = old.open_tree(&name)?;
+        if src.is_empty() {
+            continue;
+        }
+        let dst = if let Ok(filter) = filter::parse(&name) {
+            specs.insert(filter.id().as_bytes(), name.as_bytes())?;
+            db.open_tree(tree_name(filter))?
+        } else {
+            continue;
+        };
+        for kv in src.iter() {
+            let (k, v) = kv?;
+            dst.insert(k, v)?;
+        }
+    }
+    db.flush()?;
     Ok(())
 }
 
+/// Name of the sled tree storing the commits of `filter`
+fn tree_name(filter: filter::Filter) -> String {
+    format!("{}", filter.id())
+}
+
+/// Open the tree with the commits cached under `id` for `filter`, also remembering
+/// the spec and origin the first time so they can be shown in stats
+fn open_filter_tree(filter: filter::Filter, id: git2::Oid, origin: Option<&str>) -> sled::Tree {
+    let d = DB.lock().unwrap();
+    let db = d.as_ref().unwrap();
+    let specs = db.open_tree("_specs").unwrap();
+    if !specs.contains_key(id.as_bytes()).unwrap() {
+        specs
+            .insert(id.as_bytes(), filter::spec(filter).as_bytes())
+            .unwrap();
+        if let Some(origin) = origin {
+            db.open_tree("_origins")
+                .unwrap()
+                .insert(id.as_bytes(), origin.as_bytes())
+                .unwrap();
+        }
+    }
+    db.open_tree(format!("{}", id)).unwrap()
+}
+
 pub fn print_stats() {
     let d = DB.lock().unwrap();
     let db = d.as_ref().unwrap();
     db.flush().unwrap();
     log::debug!("Trees:");
+    let specs = db.open_tree("_specs").unwrap();
+    let origins = db.open_tree("_origins").unwrap();
     let mut v = vec![];
     for name in db.tree_names() {
         let name = String::from_utf8(name.to_vec()).unwrap();
         let t = db.open_tree(&name).unwrap();
-        if t.len() != 0 {
-            let name = if name.contains("SUBTRACT") {
-                name.clone()
-            } else if name.starts_with("_") {
-                name.clone()
-            } else {
-                filter::pretty(filter::parse(&name).unwrap(), 4)
+        if t.len() != 0 && name != "_specs" && name != "_origins" {
+            let lookup = |tree: &sled::Tree| {
+                git2::Oid::from_str(&name)
+                    .ok()
+                    .and_then(|id| tree.get(id.as_bytes()).unwrap())
+                    .map(|x| String::from_utf8_lossy(&x).to_string())
+            };
+            let name = match lookup(&specs) {
+                Some(spec) => match filter::parse(&spec) {
+                    Ok(filter) => filter::pretty(filter, 4),
+                    Err(_) => spec,
+                },
+                None => name.clone(),
+            };
+            let name = match lookup(&origins) {
+                Some(origin) => format!("{} (origin {})", name, origin),
+                None => name,
             };
             v.push((t.len(), name));
         }
@@ -59,7 +140,12 @@ struct Transaction2 {
     sled_trees: HashMap<git2::Oid, sled::Tree>,
     path_tree: sled::Tree,
     invert_tree: sled::Tree,
+    tag_tree: sled::Tree,
+    notes_tree: sled::Tree,
     missing: Vec<(filter::Filter, git2::Oid)>,
+    incomplete: std::collections::HashSet<(git2::Oid, git2::Oid)>,
+    missing_submodules: usize,
+    new_submodule_commits: std::collections::HashSet<git2::Oid>,
     misses: usize,
     walks: usize,
 }
@@ -68,6 +154,7 @@ pub struct Transaction {
     t2: std::cell::RefCell<Transaction2>,
     repo: git2::Repository,
     ref_prefix: String,
+    origin: Option<String>,
 }
 
 impl Transaction {
@@ -104,6 +191,20 @@ impl Transaction {
             .unwrap()
             .open_tree("_invert")
             .unwrap();
+        let tag_tree = DB
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .open_tree("_tags")
+            .unwrap();
+        let notes_tree = DB
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .open_tree("_notes")
+            .unwrap();
         Transaction {
             t2: std::cell::RefCell::new(Transaction2 {
                 commit_map: HashMap::new(),
@@ -112,17 +213,47 @@ impl Transaction {
                 sled_trees: HashMap::new(),
                 path_tree,
                 invert_tree,
+                tag_tree,
+                notes_tree,
                 missing: vec![],
+                incomplete: std::collections::HashSet::new(),
+                missing_submodules: 0,
+                new_submodule_commits: std::collections::HashSet::new(),
                 misses: 0,
                 walks: 0,
             }),
             repo,
             ref_prefix: ref_prefix.unwrap_or("").to_string(),
+            origin: None,
         }
     }
 
     pub fn clone(&self) -> JoshResult<Transaction> {
-        Transaction::open(self.repo.path(), Some(&self.ref_prefix))
+        let mut t = Transaction::open(self.repo.path(), Some(&self.ref_prefix))?;
+        t.origin = self.origin.clone();
+        Ok(t)
+    }
+
+    /// Add a `Josh-Origin: <origin>@<sha>` trailer to filtered commits, naming the
+    /// repository `origin` and the commit they were created from.
+    /// Those commits differ from the ones without trailer, so they are cached separately.
+    pub fn with_origin(mut self, origin: &str) -> Transaction {
+        self.origin = Some(origin.to_string());
+        self
+    }
+
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    /// Key of the filtered commits of `filter` in the caches
+    fn commits_id(&self, filter: filter::Filter) -> git2::Oid {
+        if let Some(origin) = &self.origin {
+            let s = format!("{}@{}", filter.id(), origin);
+            git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes()).expect("hash_object")
+        } else {
+            filter.id()
+        }
     }
 
     pub fn repo(&self) -> &git2::Repository {
@@ -227,17 +358,63 @@ impl Transaction {
         return GLOB_MAP.lock().unwrap().get(&tree).cloned();
     }
 
+    pub fn insert_blobs(&self, tree: (git2::Oid, git2::Oid), result: git2::Oid) {
+        BLOBS_MAP.lock().unwrap().entry(tree).or_insert(result);
+    }
+
+    pub fn get_blobs(&self, tree: (git2::Oid, git2::Oid)) -> Option<git2::Oid> {
+        return BLOBS_MAP.lock().unwrap().get(&tree).cloned();
+    }
+
+    pub fn insert_first_parents(
+        &self,
+        tip: git2::Oid,
+        chain: std::sync::Arc<Vec<(i64, git2::Oid)>>,
+    ) {
+        FIRST_PARENTS_MAP
+            .lock()
+            .unwrap()
+            .entry(tip)
+            .or_insert(chain);
+    }
+
+    pub fn get_first_parents(
+        &self,
+        tip: git2::Oid,
+    ) -> Option<std::sync::Arc<Vec<(i64, git2::Oid)>>> {
+        return FIRST_PARENTS_MAP.lock().unwrap().get(&tip).cloned();
+    }
+
+    pub fn insert_ancestors(
+        &self,
+        commit: git2::Oid,
+        ancestors: std::sync::Arc<std::collections::HashSet<git2::Oid>>,
+    ) {
+        ANCESTORS_MAP
+            .lock()
+            .unwrap()
+            .entry(commit)
+            .or_insert(ancestors);
+    }
+
+    pub fn get_ancestors(
+        &self,
+        commit: git2::Oid,
+    ) -> Option<std::sync::Arc<std::collections::HashSet<git2::Oid>>> {
+        return ANCESTORS_MAP.lock().unwrap().get(&commit).cloned();
+    }
+
     pub fn insert_ref(&self, filter: filter::Filter, from: git2::Oid, to: git2::Oid) {
         REF_CACHE
             .lock()
             .unwrap()
-            .entry(filter.id())
+            .entry(self.commits_id(filter))
             .or_insert_with(|| HashMap::new())
             .insert(from, to);
     }
 
     pub fn get_ref(&self, filter: filter::Filter, from: git2::Oid) -> Option<git2::Oid> {
-        if let Some(m) = REF_CACHE.lock().unwrap().get(&filter.id()) {
+        if let Some(m) = REF_CACHE.lock().unwrap().get(&self.commits_id(filter)) {
             if let Some(oid) = m.get(&from) {
                 if self.repo.odb().unwrap().exists(*oid) {
                     return Some(*oid);
@@ -256,9 +433,10 @@ impl Transaction {
     }
 
     pub fn insert(&self, filter: filter::Filter, from: git2::Oid, to: git2::Oid, store: bool) {
+        let id = self.commits_id(filter);
         let mut t2 = self.t2.borrow_mut();
         t2.commit_map
-            .entry(filter.id())
+            .entry(id)
             .or_insert_with(|| HashMap::new())
             .insert(from, to);
 
@@ -266,32 +444,63 @@ impl Transaction {
         // random extra commits (probability 1/256) to avoid long searches for filters that reduce
         // the history length by a very large factor.
         if store || from.as_bytes()[0] == 0 {
-            let t = t2.sled_trees.entry(filter.id()).or_insert_with(|| {
-                DB.lock()
-                    .unwrap()
-                    .as_ref()
-                    .unwrap()
-                    .open_tree(filter::spec(filter))
-                    .unwrap()
-            });
+            let t = t2
+                .sled_trees
+                .entry(id)
+                .or_insert_with(|| open_filter_tree(filter, id, self.origin()));
 
             t.insert(from.as_bytes(), to.as_bytes()).unwrap();
         }
     }
     pub fn len(&self, filter: filter::Filter) -> usize {
+        let id = self.commits_id(filter);
         let mut t2 = self.t2.borrow_mut();
-        let t = t2.sled_trees.entry(filter.id()).or_insert_with(|| {
-            DB.lock()
-                .unwrap()
-                .as_ref()
-                .unwrap()
-                .open_tree(filter::spec(filter))
-                .unwrap()
-        });
+        let t = t2
+            .sled_trees
+            .entry(id)
+            .or_insert_with(|| open_filter_tree(filter, id, self.origin()));
 
         return t.len();
     }
 
+    /// Count a submodule commit that was not found when inlining submodules
+    pub fn insert_missing_submodule(&self) {
+        self.t2.borrow_mut().missing_submodules += 1;
+    }
+
+    pub fn missing_submodules(&self) -> usize {
+        self.t2.borrow().missing_submodules
+    }
+
+    /// Remember a submodule commit created when unapplying `:submodules`, so it can be
+    /// pushed to the repository of the submodule
+    pub fn insert_new_submodule_commit(&self, id: git2::Oid) {
+        self.t2.borrow_mut().new_submodule_commits.insert(id);
+    }
+
+    pub fn is_new_submodule_commit(&self, id: git2::Oid) -> bool {
+        self.t2.borrow().new_submodule_commits.contains(&id)
+    }
+
+    /// Mark the result of `filter` for `from` as depending on objects that are not
+    /// present yet. It is removed from the persistent cache and only kept for this
+    /// transaction, so it is recomputed once the objects are available.
+    pub fn mark_incomplete(&self, filter: filter::Filter, from: git2::Oid) {
+        let id = self.commits_id(filter);
+        let mut t2 = self.t2.borrow_mut();
+        t2.incomplete.insert((id, from));
+        let t = t2
+            .sled_trees
+            .entry(id)
+            .or_insert_with(|| open_filter_tree(filter, id, self.origin()));
+        t.remove(from.as_bytes()).unwrap();
+    }
+
+    pub fn is_incomplete(&self, filter: filter::Filter, from: git2::Oid) -> bool {
+        let t2 = self.t2.borrow();
+        !t2.incomplete.is_empty() && t2.incomplete.contains(&(self.commits_id(filter), from))
+    }
+
     pub fn get_missing(&self) -> Vec<(filter::Filter, git2::Oid)> {
         let mut missing = self.t2.borrow().missing.clone();
         missing.sort();
@@ -301,6 +510,54 @@ impl Transaction {
         return missing;
     }
 
+    /// Filtered annotated tags are not deterministic when signed, so they are always stored.
+    /// They are kept apart from the commits, so they don't count as filtered commits.
+    pub fn insert_tag(&self, filter: filter::Filter, from: git2::Oid, to: git2::Oid) {
+        let t2 = self.t2.borrow();
+        t2.tag_tree
+            .insert(self.object_key(filter, from).as_bytes(), to.as_bytes())
+            .unwrap();
+    }
+
+    pub fn get_tag(&self, filter: filter::Filter, from: git2::Oid) -> Option<git2::Oid> {
+        let t2 = self.t2.borrow();
+        self.get_object(&t2.tag_tree, filter, from)
+    }
+
+    /// Filtered notes commits, which are built from the notes of all filtered commits
+    pub fn insert_notes(&self, filter: filter::Filter, from: git2::Oid, to: git2::Oid) {
+        let t2 = self.t2.borrow();
+        t2.notes_tree
+            .insert(self.object_key(filter, from).as_bytes(), to.as_bytes())
+            .unwrap();
+    }
+
+    pub fn get_notes(&self, filter: filter::Filter, from: git2::Oid) -> Option<git2::Oid> {
+        let t2 = self.t2.borrow();
+        self.get_object(&t2.notes_tree, filter, from)
+    }
+
+    fn object_key(&self, filter: filter::Filter, from: git2::Oid) -> git2::Oid {
+        let s = format!("{:?}", (self.commits_id(filter), from));
+        git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes()).expect("hash_object")
+    }
+
+    fn get_object(
+        &self,
+        tree: &sled::Tree,
+        filter: filter::Filter,
+        from: git2::Oid,
+    ) -> Option<git2::Oid> {
+        let oid = tree
+            .get(self.object_key(filter, from).as_bytes())
+            .unwrap()?;
+        let oid = git2::Oid::from_bytes(&oid).unwrap();
+        if self.repo.odb().unwrap().exists(oid) {
+            return Some(oid);
+        }
+        return None;
+    }
+
     pub fn known(&self, filter: filter::Filter, from: git2::Oid) -> bool {
         self.get2(filter, from).is_some()
     }
@@ -320,20 +577,17 @@ impl Transaction {
         if filter == filter::nop() {
             return Some(from);
         }
+        let id = self.commits_id(filter);
         let mut t2 = self.t2.borrow_mut();
-        if let Some(m) = t2.commit_map.get(&filter.id()) {
+        if let Some(m) = t2.commit_map.get(&id) {
             if let Some(oid) = m.get(&from).cloned() {
                 return Some(oid);
             }
         }
-        let t = t2.sled_trees.entry(filter.id()).or_insert_with(|| {
-            DB.lock()
-                .unwrap()
-                .as_ref()
-                .unwrap()
-                .open_tree(filter::spec(filter))
-                .unwrap()
-        });
+        let t = t2
+            .sled_trees
+            .entry(id)
+            .or_insert_with(|| open_filter_tree(filter, id, self.origin()));
         if let Some(oid) = t.get(from.as_bytes()).unwrap() {
             let oid = git2::Oid::from_bytes(&oid).unwrap();
             if oid == git2::Oid::zero() {
@@ -349,3 +603,34 @@ impl Transaction {
         return None;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_v6_test() {
+        let old = sled::Config::new().temporary(true).open().unwrap();
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        old.open_tree(":/sub1").unwrap().insert(b"a", b"b").unwrap();
+        old.open_tree("_paths").unwrap().insert(b"c", b"d").unwrap();
+        old.open_tree(":nosuch")
+            .unwrap()
+            .insert(b"e", b"f")
+            .unwrap();
+
+        migrate_v6(&old, &db).unwrap();
+
+        let filter = filter::parse(":/sub1").unwrap();
+        let t = db.open_tree(tree_name(filter)).unwrap();
+        assert_eq!(Some(b"b".as_ref().into()), t.get(b"a").unwrap());
+        let specs = db.open_tree("_specs").unwrap();
+        assert_eq!(
+            Some(b":/sub1".as_ref().into()),
+            specs.get(filter.id().as_bytes()).unwrap()
+        );
+        // Only the default tree, `_specs` and the tree of `:/sub1`. `_paths` is keyed
+        // by the old ids, so it is not useful anymore
+        assert_eq!(3, db.tree_names().len());
+    }
+}
@@ -0,0 +1,473 @@
+/*
+ * Public representation of the structure of a filter.
+ * Unlike `Op` it is meant to be created and inspected by code outside of josh,
+ * so it can be (de)serialized and its variants will only be extended.
+ */
+
+use super::*;
+use std::path::PathBuf;
+
+/// The syntax tree of a filter, mirroring the filter spec language.
+/// Serialized with serde, a filter like `:/a:[::b/,c=:/d]` looks like this in YAML:
+///
+/// ```yaml
+/// chain:
+///   - subdir: a
+///   - compose:
+///       - chain:
+///           - subdir: b
+///           - prefix: b
+///       - chain:
+///           - subdir: d
+///           - prefix: c
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ast {
+    Nop,
+    Empty,
+    Fold,
+    Squash,
+    /// Squash the history before the boundary, keep the history after it
+    SquashBefore(SquashBoundary),
+    Linear,
+    Paths,
+    Submodules,
+
+    File {
+        dst: PathBuf,
+        src: PathBuf,
+    },
+    Prefix(PathBuf),
+    Subdir(PathBuf),
+    Workspace(PathBuf),
+    Include(PathBuf),
+    Repo {
+        repo: String,
+        refname: String,
+    },
+    Authors(PathBuf),
+    Message(String),
+    StripMessage(String),
+
+    Glob(String),
+    Regex(String),
+    Patterns(Vec<String>),
+    PatternsFile(PathBuf),
+    Replace {
+        pattern: String,
+        regex: String,
+        replacement: String,
+    },
+    MaxSize {
+        size: usize,
+        #[serde(default)]
+        stub: bool,
+    },
+    Attr(String),
+
+    Compose(Vec<Ast>),
+    /// The output of each filter is the input of the next one
+    Chain(Vec<Ast>),
+    /// Content of `filter` that is not in `subtract`
+    Subtract {
+        filter: Box<Ast>,
+        subtract: Box<Ast>,
+    },
+}
+
+impl Ast {
+    pub fn subdir<P: Into<PathBuf>>(path: P) -> Ast {
+        Ast::Subdir(path.into())
+    }
+
+    pub fn prefix<P: Into<PathBuf>>(path: P) -> Ast {
+        Ast::Prefix(path.into())
+    }
+
+    /// Select the file `src` and put it at `dst`
+    pub fn file<P: Into<PathBuf>, Q: Into<PathBuf>>(dst: P, src: Q) -> Ast {
+        Ast::File {
+            dst: dst.into(),
+            src: src.into(),
+        }
+    }
+
+    /// Equivalent to `::path/`: Select the directory `path` and keep it at the same place
+    pub fn dir<P: Into<PathBuf>>(path: P) -> Ast {
+        let path = path.into();
+        Ast::Chain(vec![Ast::Subdir(path.clone()), Ast::Prefix(path)])
+    }
+
+    pub fn workspace<P: Into<PathBuf>>(path: P) -> Ast {
+        Ast::Workspace(path.into())
+    }
+
+    pub fn chain<I: IntoIterator<Item = Ast>>(filters: I) -> Ast {
+        Ast::Chain(filters.into_iter().collect())
+    }
+
+    pub fn compose<I: IntoIterator<Item = Ast>>(filters: I) -> Ast {
+        Ast::Compose(filters.into_iter().collect())
+    }
+
+    pub fn subtract(filter: Ast, subtract: Ast) -> Ast {
+        Ast::Subtract {
+            filter: Box::new(filter),
+            subtract: Box::new(subtract),
+        }
+    }
+
+    /// Equivalent to `:exclude[...]`: Everything except the output of `filter`
+    pub fn exclude(filter: Ast) -> Ast {
+        Ast::subtract(Ast::Nop, filter)
+    }
+
+    /// Append `next` to a chain starting with `self`
+    pub fn then(self, next: Ast) -> Ast {
+        match self {
+            Ast::Chain(mut filters) => {
+                filters.push(next);
+                Ast::Chain(filters)
+            }
+            first => Ast::Chain(vec![first, next]),
+        }
+    }
+
+    /// Like `then`, but also flattens `next` if it is a chain
+    fn then_all(self, next: Ast) -> Ast {
+        match next {
+            Ast::Chain(filters) => filters.into_iter().fold(self, Ast::then),
+            next => self.then(next),
+        }
+    }
+
+    /// Create the `Filter` described by the syntax tree. The result is the same
+    /// as parsing the equivalent spec, and arguments are validated the same way.
+    pub fn to_filter(&self) -> JoshResult<Filter> {
+        Ok(opt::optimize(to_filter(from_ast(self)?)))
+    }
+
+    /// Children of `Compose`, `Chain` and `Subtract` nodes
+    pub fn children(&self) -> Vec<&Ast> {
+        match self {
+            Ast::Compose(filters) | Ast::Chain(filters) => filters.iter().collect(),
+            Ast::Subtract { filter, subtract } => vec![filter, subtract],
+            _ => vec![],
+        }
+    }
+}
+
+impl std::convert::From<Filter> for Ast {
+    fn from(filter: Filter) -> Ast {
+        to_ast(&to_op(opt::simplify(filter)))
+    }
+}
+
+/// Visitor for walking a syntax tree. The default implementation of `visit`
+/// visits all nodes in depth first order, implementations that override it
+/// can call `walk` to continue with the children.
+pub trait Visitor {
+    fn visit(&mut self, ast: &Ast) {
+        walk(self, ast);
+    }
+}
+
+/// Visit the children of `ast`
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, ast: &Ast) {
+    for child in ast.children() {
+        visitor.visit(child);
+    }
+}
+
+fn to_ast(op: &Op) -> Ast {
+    match op {
+        Op::Nop => Ast::Nop,
+        Op::Empty => Ast::Empty,
+        Op::Fold => Ast::Fold,
+        Op::Squash(None) => Ast::Squash,
+        Op::Squash(Some(boundary)) => Ast::SquashBefore(boundary.clone()),
+        Op::Linear => Ast::Linear,
+        Op::Paths => Ast::Paths,
+        Op::Submodules => Ast::Submodules,
+        Op::File(dst, src) => Ast::file(dst, src),
+        Op::Prefix(path) => Ast::Prefix(path.clone()),
+        Op::Subdir(path) => Ast::Subdir(path.clone()),
+        Op::Workspace(path) => Ast::Workspace(path.clone()),
+        Op::Include(path) => Ast::Include(path.clone()),
+        Op::Repo(repo, refname) => Ast::Repo {
+            repo: repo.clone(),
+            refname: refname.clone(),
+        },
+        Op::Authors(path) => Ast::Authors(path.clone()),
+        Op::Message(template) => Ast::Message(template.clone()),
+        Op::StripMessage(regex) => Ast::StripMessage(regex.clone()),
+        Op::Glob(pattern) => Ast::Glob(pattern.clone()),
+        Op::Regex(regex) => Ast::Regex(regex.clone()),
+        Op::Patterns(patterns) => Ast::Patterns(patterns.clone()),
+        Op::PatternsFile(path) => Ast::PatternsFile(path.clone()),
+        Op::Replace(pattern, regex, replacement) => Ast::Replace {
+            pattern: pattern.clone(),
+            regex: regex.clone(),
+            replacement: replacement.clone(),
+        },
+        Op::MaxSize(size, stub) => Ast::MaxSize {
+            size: *size,
+            stub: *stub,
+        },
+        Op::Attr(attr) => Ast::Attr(attr.clone()),
+        Op::Compose(filters) => Ast::Compose(filters.iter().map(|f| to_ast(&to_op(*f))).collect()),
+        Op::Chain(a, b) => to_ast(&to_op(*a)).then_all(to_ast(&to_op(*b))),
+        Op::Subtract(a, b) => Ast::subtract(to_ast(&to_op(*a)), to_ast(&to_op(*b))),
+    }
+}
+
+fn from_ast(ast: &Ast) -> JoshResult<Op> {
+    let op = match ast {
+        Ast::Nop => Op::Nop,
+        Ast::Empty => Op::Empty,
+        Ast::Fold => Op::Fold,
+        Ast::Squash => Op::Squash(None),
+        Ast::SquashBefore(boundary) => Op::Squash(Some(boundary.clone())),
+        Ast::Linear => Op::Linear,
+        Ast::Paths => Op::Paths,
+        Ast::Submodules => Op::Submodules,
+        Ast::File { dst, src } => Op::File(dst.clone(), src.clone()),
+        Ast::Prefix(path) => Op::Prefix(path.clone()),
+        Ast::Subdir(path) => Op::Subdir(path.clone()),
+        Ast::Workspace(path) => Op::Workspace(path.clone()),
+        Ast::Include(path) => Op::Include(path.clone()),
+        Ast::Repo { repo, refname } => Op::Repo(repo.clone(), refname.clone()),
+        Ast::Authors(path) => Op::Authors(path.clone()),
+        Ast::Message(template) => Op::Message(template.clone()),
+        Ast::StripMessage(regex) => Op::StripMessage(regex.clone()),
+        Ast::Glob(pattern) => Op::Glob(pattern.clone()),
+        Ast::Regex(regex) => Op::Regex(regex.clone()),
+        Ast::Patterns(patterns) => Op::Patterns(patterns.clone()),
+        Ast::PatternsFile(path) => Op::PatternsFile(path.clone()),
+        Ast::Replace {
+            pattern,
+            regex,
+            replacement,
+        } => Op::Replace(pattern.clone(), regex.clone(), replacement.clone()),
+        Ast::MaxSize { size, stub } => Op::MaxSize(*size, *stub),
+        Ast::Attr(attr) => Op::Attr(attr.clone()),
+        Ast::Compose(filters) => Op::Compose(
+            filters
+                .iter()
+                .map(|f| Ok(to_filter(from_ast(f)?)))
+                .collect::<JoshResult<_>>()?,
+        ),
+        Ast::Chain(filters) => {
+            let mut chain = None;
+            for f in filters {
+                let op = from_ast(f)?;
+                chain = Some(match chain {
+                    Some(c) => Op::Chain(to_filter(c), to_filter(op)),
+                    None => op,
+                });
+            }
+            chain.unwrap_or(Op::Nop)
+        }
+        Ast::Subtract { filter, subtract } => {
+            Op::Subtract(to_filter(from_ast(filter)?), to_filter(from_ast(subtract)?))
+        }
+    };
+    parse::validate(&op)?;
+    Ok(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_roundtrip_test() {
+        for spec in &[
+            ":/",
+            ":empty",
+            ":FOLD",
+            ":SQUASH",
+            ":SQUASH=2021-01-01",
+            ":linear",
+            ":PATHS",
+            ":submodules",
+            ":/a/b",
+            ":prefix=a",
+            "::a/b",
+            "::a=b",
+            "::*.txt",
+            ":workspace=ws",
+            ":include=shared/f.josh",
+            ":repo=other.git@refs/heads/master",
+            ":authors=authors.txt",
+            ":message=\"{subject}\"",
+            ":strip_message=\"^x\"",
+            ":regex=\"a.*\"",
+            ":patterns=\"*.c\",\"!b/\"",
+            ":patterns_file=p.txt",
+            ":replace=\"*.txt\",\"a\",\"b\"",
+            ":maxsize=10",
+            ":maxsize=10,stub",
+            ":attr=export",
+            ":/a:[::b/,c=:/d]:prefix=x",
+            ":exclude[::e]",
+            ":subtract[:/a,::b]",
+        ] {
+            let filter = parse(spec).unwrap();
+            let ast = Ast::from(filter);
+            assert_eq!(filter, ast.to_filter().unwrap(), "{}", spec);
+            assert_eq!(ast, Ast::from(ast.to_filter().unwrap()), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn structure_test() {
+        let filter = parse(":/a:[::b/,c=:/d]:exclude[::e]").unwrap();
+        assert_eq!(
+            Ast::chain(vec![
+                Ast::subdir("a"),
+                Ast::compose(vec![Ast::dir("b"), Ast::subdir("d").then(Ast::prefix("c"))]),
+                Ast::exclude(Ast::file("e", "e")),
+            ]),
+            Ast::from(filter)
+        );
+    }
+
+    #[test]
+    fn validate_test() {
+        for invalid in &[
+            r#"{"regex":"("}"#,
+            r#"{"strip_message":"("}"#,
+            r#"{"message":"{nosuch}"}"#,
+            r#"{"patterns":[]}"#,
+            r#"{"repo":{"repo":"other.git","refname":""}}"#,
+            r#"{"chain":[{"subdir":"a"},{"compose":[{"regex":"("}]}]}"#,
+        ] {
+            let ast: Ast = serde_json::from_str(invalid).unwrap();
+            assert!(ast.to_filter().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn serde_roundtrip_test() {
+        let ast = Ast::chain(vec![
+            Ast::Nop,
+            Ast::Empty,
+            Ast::Fold,
+            Ast::Squash,
+            Ast::SquashBefore(SquashBoundary::Time(1609459200)),
+            Ast::SquashBefore(SquashBoundary::Commit("0".repeat(40))),
+            Ast::Linear,
+            Ast::Paths,
+            Ast::Submodules,
+            Ast::file("a", "b"),
+            Ast::prefix("a"),
+            Ast::subdir("a"),
+            Ast::workspace("ws"),
+            Ast::Include("f.josh".into()),
+            Ast::Repo {
+                repo: "other.git".to_string(),
+                refname: "refs/heads/master".to_string(),
+            },
+            Ast::Authors("authors.txt".into()),
+            Ast::Message("{subject}".to_string()),
+            Ast::StripMessage("^x".to_string()),
+            Ast::Glob("*.txt".to_string()),
+            Ast::Regex("a.*".to_string()),
+            Ast::Patterns(vec!["*.c".to_string(), "!b/".to_string()]),
+            Ast::PatternsFile("p.txt".into()),
+            Ast::Replace {
+                pattern: "*.txt".to_string(),
+                regex: "a".to_string(),
+                replacement: "b".to_string(),
+            },
+            Ast::MaxSize {
+                size: 10,
+                stub: true,
+            },
+            Ast::Attr("export".to_string()),
+            Ast::compose(vec![
+                Ast::dir("b"),
+                Ast::exclude(Ast::Glob("c".to_string())),
+            ]),
+        ]);
+
+        let json = serde_json::to_string(&ast).unwrap();
+        assert_eq!(ast, serde_json::from_str::<Ast>(&json).unwrap());
+        let yaml = serde_yaml::to_string(&ast).unwrap();
+        assert_eq!(ast, serde_yaml::from_str::<Ast>(&yaml).unwrap());
+
+        assert_eq!(
+            r#"{"chain":[{"subdir":"a"},{"compose":[{"file":{"dst":"b","src":"b"}}]}]}"#,
+            serde_json::to_string(&Ast::chain(vec![
+                Ast::subdir("a"),
+                Ast::compose(vec![Ast::file("b", "b")])
+            ]))
+            .unwrap()
+        );
+        assert_eq!(
+            Ast::MaxSize {
+                size: 10,
+                stub: false
+            },
+            serde_json::from_str(r#"{"max_size":{"size":10}}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn visitor_order_test() {
+        struct Collect(Vec<Ast>);
+        impl Visitor for Collect {
+            fn visit(&mut self, ast: &Ast) {
+                if ast.children().is_empty() {
+                    self.0.push(ast.clone());
+                }
+                walk(self, ast);
+            }
+        }
+
+        // Subtrees that are not visited when `walk` is not called
+        struct Skip(Vec<Ast>);
+        impl Visitor for Skip {
+            fn visit(&mut self, ast: &Ast) {
+                self.0.push(ast.clone());
+                if let Ast::Compose(_) = ast {
+                    return;
+                }
+                walk(self, ast);
+            }
+        }
+
+        let ast = Ast::from(parse(":/a:[::b/,c=:/d]:subtract[::e,::f]").unwrap());
+
+        let mut collect = Collect(vec![]);
+        collect.visit(&ast);
+        assert_eq!(
+            vec![
+                Ast::subdir("a"),
+                Ast::subdir("b"),
+                Ast::prefix("b"),
+                Ast::subdir("d"),
+                Ast::prefix("c"),
+                Ast::file("e", "e"),
+                Ast::file("f", "f"),
+            ],
+            collect.0
+        );
+
+        let mut skip = Skip(vec![]);
+        skip.visit(&ast);
+        assert_eq!(
+            vec![
+                ast.clone(),
+                Ast::subdir("a"),
+                ast.children()[1].clone(),
+                ast.children()[2].clone(),
+                Ast::file("e", "e"),
+                Ast::file("f", "f"),
+            ],
+            skip.0
+        );
+    }
+}
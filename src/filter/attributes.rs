@@ -0,0 +1,86 @@
+/*
+ * Evaluation of a single attribute defined in `.gitattributes` files
+ * (see https://git-scm.com/docs/gitattributes).
+ */
+
+use super::*;
+
+#[derive(Clone)]
+struct Rule {
+    base: String,
+    regex: regex::Regex,
+    set: bool,
+}
+
+/// The rules for one attribute collected from the `.gitattributes` files of a
+/// directory and all of its parents.
+#[derive(Default, Clone)]
+pub struct Attributes {
+    rules: Vec<Rule>,
+    sources: Vec<(String, git2::Oid)>,
+}
+
+impl Attributes {
+    /// Add the rules for `attr` from a `.gitattributes` file located in the directory `base`.
+    /// Like in git, `attr` and `attr=value` set the attribute, `-attr` unsets it and `!attr`
+    /// makes it unspecified again. Macro definitions and patterns starting with `!` are ignored.
+    pub fn with_file(&self, base: &str, id: git2::Oid, content: &str, attr: &str) -> Attributes {
+        let mut rules = vec![];
+        for line in content.lines() {
+            let mut tokens = line.split_whitespace();
+            let pattern = some_or!(tokens.next(), { continue });
+            if pattern.starts_with('#') || pattern.starts_with('!') || pattern.starts_with("[attr]")
+            {
+                continue;
+            }
+            for token in tokens {
+                let set = if token == attr || token.starts_with(&format!("{}=", attr)) {
+                    true
+                } else if token.trim_start_matches(|c| c == '-' || c == '!') == attr {
+                    false
+                } else {
+                    continue;
+                };
+                let regex = patterns::to_regex(pattern.trim_end_matches('/'));
+                let regex = ok_or!(regex::Regex::new(&regex), { continue });
+                rules.push(Rule {
+                    base: base.to_string(),
+                    regex,
+                    set,
+                });
+            }
+        }
+
+        let mut result = self.clone();
+        if !rules.is_empty() {
+            result.rules.extend(rules);
+            result.sources.push((base.to_string(), id));
+        }
+        return result;
+    }
+
+    /// Check if the attribute is set for the file `path`. The last matching rule decides,
+    /// rules from deeper directories override the ones from their parents.
+    /// As in git, rules matching a directory don't apply to the files inside of it.
+    pub fn is_set(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .rev()
+            .find(|r| {
+                let rel = if r.base == "" {
+                    Some(path)
+                } else {
+                    path.strip_prefix(&r.base).and_then(|p| p.strip_prefix('/'))
+                };
+                rel.map(|p| r.regex.is_match(p)).unwrap_or(false)
+            })
+            .map(|r| r.set)
+            .unwrap_or(false)
+    }
+
+    /// Identifies the `.gitattributes` files the rules were taken from, used as part
+    /// of cache keys.
+    pub fn sources(&self) -> &[(String, git2::Oid)] {
+        &self.sources
+    }
+}
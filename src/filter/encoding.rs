@@ -0,0 +1,168 @@
+/*
+ * Canonical binary encoding of filters. The id of a filter is the git blob hash
+ * of its encoding, so the encoding must never change for existing operations:
+ * Ids are used as names of the persistent caches and of refs.
+ *
+ * A filter is encoded as one byte identifying the operation, followed by its
+ * arguments in order:
+ *
+ *   strings, paths   u32 little endian length in bytes, then the UTF-8 bytes
+ *   numbers          u64 little endian
+ *   flags            one byte, 0 or 1
+ *   filters          the 20 byte id of the filter
+ *   boundaries       0 and the commit sha as string or 1 and the time as number
+ *   lists            u32 little endian number of items, then the items
+ *
+ * Operation bytes:
+ *
+ *   0x00 Nop         0x10 File(dst, src)     0x20 Glob(pattern)
+ *   0x01 Empty       0x11 Prefix(path)       0x21 Regex(regex)
+ *   0x02 Fold        0x12 Subdir(path)       0x22 Patterns([pattern])
+ *   0x03 Squash      0x13 Workspace(path)    0x23 PatternsFile(path)
+ *   0x04 Linear      0x14 Include(path)      0x24 Replace(pattern, regex, replacement)
+ *   0x05 Paths       0x15 Repo(repo, ref)    0x25 MaxSize(size, stub)
+ *   0x06 Submodules  0x16 Authors(path)      0x26 Attr(name)
+ *                    0x17 Message(template)
+ *                    0x18 StripMessage(regex)
+ *                    0x19 Squash(boundary)
+ *
+ *   0x40 Compose([filter])
+ *   0x41 Chain(filter, filter)
+ *   0x42 Subtract(filter, filter)
+ *
+ * New operations get new bytes, existing ones are never reused.
+ */
+
+use super::*;
+
+pub fn encode(op: &Op) -> Vec<u8> {
+    let mut out = vec![];
+    match op {
+        Op::Nop => out.push(0x00),
+        Op::Empty => out.push(0x01),
+        Op::Fold => out.push(0x02),
+        Op::Squash(None) => out.push(0x03),
+        Op::Linear => out.push(0x04),
+        Op::Paths => out.push(0x05),
+        Op::Submodules => out.push(0x06),
+
+        Op::File(dst, src) => {
+            out.push(0x10);
+            path(&mut out, dst);
+            path(&mut out, src);
+        }
+        Op::Prefix(p) => {
+            out.push(0x11);
+            path(&mut out, p);
+        }
+        Op::Subdir(p) => {
+            out.push(0x12);
+            path(&mut out, p);
+        }
+        Op::Workspace(p) => {
+            out.push(0x13);
+            path(&mut out, p);
+        }
+        Op::Include(p) => {
+            out.push(0x14);
+            path(&mut out, p);
+        }
+        Op::Repo(repo, refname) => {
+            out.push(0x15);
+            string(&mut out, repo);
+            string(&mut out, refname);
+        }
+        Op::Authors(p) => {
+            out.push(0x16);
+            path(&mut out, p);
+        }
+        Op::Message(template) => {
+            out.push(0x17);
+            string(&mut out, template);
+        }
+        Op::StripMessage(regex) => {
+            out.push(0x18);
+            string(&mut out, regex);
+        }
+        Op::Squash(Some(boundary)) => {
+            out.push(0x19);
+            match boundary {
+                SquashBoundary::Commit(sha) => {
+                    out.push(0);
+                    string(&mut out, sha);
+                }
+                SquashBoundary::Time(time) => {
+                    out.push(1);
+                    out.extend_from_slice(&(*time as u64).to_le_bytes());
+                }
+            }
+        }
+
+        Op::Glob(pattern) => {
+            out.push(0x20);
+            string(&mut out, pattern);
+        }
+        Op::Regex(regex) => {
+            out.push(0x21);
+            string(&mut out, regex);
+        }
+        Op::Patterns(patterns) => {
+            out.push(0x22);
+            length(&mut out, patterns.len());
+            for pattern in patterns {
+                string(&mut out, pattern);
+            }
+        }
+        Op::PatternsFile(p) => {
+            out.push(0x23);
+            path(&mut out, p);
+        }
+        Op::Replace(pattern, regex, replacement) => {
+            out.push(0x24);
+            string(&mut out, pattern);
+            string(&mut out, regex);
+            string(&mut out, replacement);
+        }
+        Op::MaxSize(size, stub) => {
+            out.push(0x25);
+            out.extend_from_slice(&(*size as u64).to_le_bytes());
+            out.push(if *stub { 1 } else { 0 });
+        }
+        Op::Attr(name) => {
+            out.push(0x26);
+            string(&mut out, name);
+        }
+
+        Op::Compose(filters) => {
+            out.push(0x40);
+            length(&mut out, filters.len());
+            for filter in filters {
+                out.extend_from_slice(filter.id().as_bytes());
+            }
+        }
+        Op::Chain(a, b) => {
+            out.push(0x41);
+            out.extend_from_slice(a.id().as_bytes());
+            out.extend_from_slice(b.id().as_bytes());
+        }
+        Op::Subtract(a, b) => {
+            out.push(0x42);
+            out.extend_from_slice(a.id().as_bytes());
+            out.extend_from_slice(b.id().as_bytes());
+        }
+    }
+    return out;
+}
+
+fn length(out: &mut Vec<u8>, len: usize) {
+    out.extend_from_slice(&(len as u32).to_le_bytes());
+}
+
+fn string(out: &mut Vec<u8>, s: &str) {
+    length(out, s.len());
+    out.extend_from_slice(s.as_bytes());
+}
+
+fn path(out: &mut Vec<u8>, p: &std::path::Path) {
+    string(out, &p.to_string_lossy());
+}
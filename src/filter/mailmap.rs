@@ -0,0 +1,129 @@
+/*
+ * Minimal mailmap implementation used by the `:authors=` filter.
+ * Unlike `git2::Mailmap` it gives access to the entries, which is needed to
+ * map identities back when pushing.
+ */
+
+use super::*;
+
+lazy_static! {
+    static ref LINE: regex::Regex =
+        regex::Regex::new(r"^\s*([^<#]*?)\s*<([^>]*)>\s*(?:([^<#]*?)\s*<([^>]*)>)?\s*(?:#.*)?$")
+            .expect("can't compile regex");
+}
+
+/// One line of a mailmap file: Signatures matching `old_name` (if given) and
+/// `old_email` get their name replaced by `new_name` (if given) and their email
+/// replaced by `new_email` (if given).
+struct Entry {
+    new_name: Option<String>,
+    new_email: Option<String>,
+    old_name: Option<String>,
+    old_email: String,
+}
+
+pub struct Mailmap(Vec<Entry>);
+
+fn non_empty(s: Option<regex::Match>) -> Option<String> {
+    s.map(|x| x.as_str().to_string()).filter(|x| x != "")
+}
+
+fn eq(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+fn name_email(sig: &git2::Signature) -> (String, String) {
+    (
+        String::from_utf8_lossy(sig.name_bytes()).to_string(),
+        String::from_utf8_lossy(sig.email_bytes()).to_string(),
+    )
+}
+
+impl Mailmap {
+    /// Parse the content of a mailmap file. Lines that can not be parsed are ignored,
+    /// the same way git does.
+    pub fn parse(content: &str) -> Mailmap {
+        let mut entries = vec![];
+        for line in content.lines() {
+            let caps = some_or!(LINE.captures(line), { continue });
+            let entry = if let Some(old_email) = caps.get(4) {
+                Entry {
+                    new_name: non_empty(caps.get(1)),
+                    new_email: non_empty(caps.get(2)),
+                    old_name: non_empty(caps.get(3)),
+                    old_email: old_email.as_str().to_string(),
+                }
+            } else {
+                Entry {
+                    new_name: non_empty(caps.get(1)),
+                    new_email: None,
+                    old_name: None,
+                    old_email: caps[2].to_string(),
+                }
+            };
+            entries.push(entry);
+        }
+        Mailmap(entries)
+    }
+
+    /// Map `sig` to the identity given by the mailmap. Returns `None` if no entry applies.
+    pub fn map(&self, sig: &git2::Signature) -> JoshResult<Option<git2::Signature<'static>>> {
+        let (name, email) = name_email(sig);
+
+        // Entries that also match on the name take precedence, later entries override
+        // earlier ones.
+        let entry = self
+            .0
+            .iter()
+            .rev()
+            .filter(|e| eq(&e.old_email, &email))
+            .find(|e| e.old_name.as_ref().map(|n| eq(n, &name)).unwrap_or(false))
+            .or_else(|| {
+                self.0
+                    .iter()
+                    .rev()
+                    .find(|e| e.old_name.is_none() && eq(&e.old_email, &email))
+            });
+
+        let entry = some_or!(entry, { return Ok(None) });
+
+        Ok(Some(git2::Signature::new(
+            entry.new_name.as_ref().unwrap_or(&name),
+            entry.new_email.as_ref().unwrap_or(&email),
+            &sig.when(),
+        )?))
+    }
+
+    /// Inverse of `map`: Find the identity that was mapped to `sig`.
+    /// Returns `None` if `sig` is not the result of a mapping or if the mapping is
+    /// not unique because several identities are mapped to the same one.
+    pub fn unmap(&self, sig: &git2::Signature) -> JoshResult<Option<git2::Signature<'static>>> {
+        let (name, email) = name_email(sig);
+
+        let mut candidates = self
+            .0
+            .iter()
+            .filter(|e| eq(e.new_email.as_ref().unwrap_or(&e.old_email), &email))
+            .filter(|e| e.new_name.as_ref().map(|n| n == &name).unwrap_or(true))
+            .map(|e| {
+                (
+                    e.old_name.as_ref().unwrap_or(&name).to_lowercase(),
+                    e.old_email.to_lowercase(),
+                    e,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        candidates.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        candidates.dedup_by(|a, b| (&a.0, &a.1) == (&b.0, &b.1));
+
+        if let [(_, _, entry)] = candidates.as_slice() {
+            return Ok(Some(git2::Signature::new(
+                entry.old_name.as_ref().unwrap_or(&name),
+                &entry.old_email,
+                &sig.when(),
+            )?));
+        }
+        Ok(None)
+    }
+}
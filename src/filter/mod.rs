@@ -1,12 +1,19 @@
 use super::*;
 use pest::Parser;
 use std::path::Path;
+pub mod ast;
+mod attributes;
+mod encoding;
+mod mailmap;
 mod opt;
 mod parse;
+mod patterns;
 pub mod tree;
 
 pub use parse::get_comments;
 pub use parse::parse;
+pub use parse::try_parse;
+pub use parse::ParseError;
 
 lazy_static! {
     static ref FILTERS: std::sync::Mutex<std::collections::HashMap<Filter, Op>> =
@@ -14,7 +21,8 @@ lazy_static! {
 }
 
 /// Filters are represented as `git2::Oid`, however they are not ever stored
-/// inside the repo.
+/// inside the repo. The id is the hash of a canonical encoding of the filter
+/// (see `encoding.rs`) and stays the same across versions of josh.
 #[derive(Clone, Hash, PartialEq, Eq, Debug, Copy, PartialOrd, Ord)]
 pub struct Filter(git2::Oid);
 
@@ -29,9 +37,9 @@ pub fn nop() -> Filter {
 }
 
 fn to_filter(op: Op) -> Filter {
-    let s = format!("{:?}", op);
     let f = Filter(
-        git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes()).expect("hash_object filter"),
+        git2::Oid::hash_object(git2::ObjectType::Blob, &encoding::encode(&op))
+            .expect("hash_object filter"),
     );
     FILTERS.lock().unwrap().insert(f, op);
     return f;
@@ -46,20 +54,44 @@ fn to_op(filter: Filter) -> Op {
         .clone()
 }
 
+/// Where the history kept by `:SQUASH=...` starts. Everything before it is squashed
+/// into a single root commit.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SquashBoundary {
+    /// The commit (given by its full sha) and all of its ancestors are squashed
+    Commit(String),
+    /// Commits with a commit time before the given unix time are squashed
+    Time(i64),
+}
+
 #[derive(Clone, Debug)]
 enum Op {
     Nop,
     Empty,
     Fold,
-    Squash,
+    Squash(Option<SquashBoundary>),
+    Linear,
     Paths,
 
-    File(std::path::PathBuf),
+    File(std::path::PathBuf, std::path::PathBuf),
     Prefix(std::path::PathBuf),
     Subdir(std::path::PathBuf),
     Workspace(std::path::PathBuf),
+    Include(std::path::PathBuf),
+    Repo(String, String),
+    Authors(std::path::PathBuf),
+    Message(String),
+    StripMessage(String),
 
     Glob(String),
+    Regex(String),
+    Patterns(Vec<String>),
+    PatternsFile(std::path::PathBuf),
+    Replace(String, String, String),
+    MaxSize(usize, bool),
+    Attr(String),
+    Submodules,
 
     Compose(Vec<Filter>),
     Chain(Filter, Filter),
@@ -76,21 +108,61 @@ pub fn pretty(filter: Filter, indent: usize) -> String {
             let i = format!("\n{}", " ".repeat(indent));
             return filters
                 .iter()
-                .map(|x| pretty2(&to_op(*x), indent + 4, true))
+                .map(|x| pretty2(&to_op(*x), indent + 4, true, &[]))
                 .collect::<Vec<_>>()
                 .join(&i);
         }
     }
-    return pretty2(&to_op(filter), indent, true);
+    return pretty2(&to_op(filter), indent, true, &[]);
+}
+
+/// Pretty print a workspace file like `pretty`, but keep the definitions of named
+/// filters. Parts of the filter that are equal to a named filter are printed as
+/// references to it.
+pub fn pretty_workspace(filter_spec: &str) -> JoshResult<String> {
+    let (definitions, filter) = parse::parse_with_definitions(filter_spec)?;
+    if definitions.is_empty() {
+        return Ok(pretty(filter, 0));
+    }
+    let names: Vec<_> = definitions
+        .iter()
+        .map(|(name, f)| (opt::simplify(*f), name.clone()))
+        .collect();
+    let mut lines = vec![];
+    for (i, (f, name)) in names.iter().enumerate() {
+        lines.push(format!(
+            "${} = {}",
+            name,
+            pretty2(&to_op(*f), 0, false, &names[..i])
+        ));
+    }
+    let filter = opt::simplify(filter);
+    let body = if let Op::Compose(filters) = to_op(filter) {
+        filters
+            .iter()
+            .map(|x| pretty2(&to_op(*x), 4, true, &names))
+            .collect::<Vec<_>>()
+            .join("\n")
+    } else {
+        pretty2(&to_op(filter), 0, true, &names)
+    };
+    Ok(format!("{}\n\n{}", lines.join("\n"), body))
 }
 
-fn pretty2(op: &Op, indent: usize, compose: bool) -> String {
+/// `names` are named filters that are printed as references
+fn pretty2(op: &Op, indent: usize, compose: bool, names: &[(Filter, String)]) -> String {
+    if !names.is_empty() {
+        let filter = to_filter(op.clone());
+        if let Some((_, name)) = names.iter().find(|(f, _)| *f == filter) {
+            return format!("${}", name);
+        }
+    }
     let ff = |filters: &Vec<_>, n, ind| {
         let ind2 = std::cmp::max(ind, 4);
         let i = format!("\n{}", " ".repeat(ind2));
         let joined = filters
             .iter()
-            .map(|x| pretty2(&to_op(*x), ind + 4, true))
+            .map(|x| pretty2(&to_op(*x), ind + 4, true, names))
             .collect::<Vec<_>>()
             .join(&i);
 
@@ -106,20 +178,25 @@ fn pretty2(op: &Op, indent: usize, compose: bool) -> String {
         Op::Compose(filters) => ff(filters, "", indent),
         Op::Subtract(af, bf) => match (to_op(*af), to_op(*bf)) {
             (Op::Nop, Op::Compose(filters)) => ff(&filters, "exclude", indent),
-            (Op::Nop, b) => format!(":exclude[{}]", pretty2(&b, indent, false)),
+            (Op::Nop, b) => format!(":exclude[{}]", pretty2(&b, indent, false, names)),
             _ => ff(&vec![*af, *bf], "subtract", indent + 4),
         },
         Op::Chain(a, b) => match (to_op(*a), to_op(*b)) {
-            (Op::Subdir(p1), Op::Prefix(p2)) if p1 == p2 => {
-                format!("::{}/", p1.to_string_lossy())
+            // Named filters are printed as reference, so `lib = $common` stays as it is
+            (Op::Subdir(p1), Op::Prefix(p2)) if p1 == p2 && !names.iter().any(|(f, _)| f == a) => {
+                format!("::{}", quote_arg(&format!("{}/", p1.to_string_lossy())))
             }
             (a, Op::Prefix(p)) if compose => {
-                format!("{} = {}", p.to_string_lossy(), pretty2(&a, indent, false))
+                format!(
+                    "{} = {}",
+                    p.to_string_lossy(),
+                    pretty2(&a, indent, false, names)
+                )
             }
             (a, b) => format!(
                 "{}{}",
-                pretty2(&a, indent, false),
-                pretty2(&b, indent, false)
+                pretty2(&a, indent, false, names),
+                pretty2(&b, indent, false, names)
             ),
         },
         _ => spec2(op),
@@ -133,6 +210,32 @@ pub fn spec(filter: Filter) -> String {
     spec2(&to_op(filter))
 }
 
+/// Quote a string argument, escaping quotes and the backslashes that would otherwise
+/// be read as escape, see `parse::unquote`
+fn quote(s: &str) -> String {
+    let mut out = "\"".to_string();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('"', _) => out.push_str("\\\""),
+            ('\\', None) | ('\\', Some('\\')) | ('\\', Some('"')) => out.push_str("\\\\"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quote `s` only if it contains characters that are not allowed in unquoted arguments
+fn quote_arg(s: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "_-+.*/@".contains(c);
+    if s.chars().all(plain) {
+        s.to_string()
+    } else {
+        quote(s)
+    }
+}
+
 fn spec2(op: &Op) -> String {
     match op {
         Op::Compose(filters) => {
@@ -149,12 +252,21 @@ fn spec2(op: &Op) -> String {
             format!(":subtract[{},{}]", spec(*a), spec(*b))
         }
         Op::Workspace(path) => {
-            format!(":workspace={}", path.to_string_lossy())
+            format!(":workspace={}", quote_arg(&path.to_string_lossy()))
+        }
+        Op::Include(path) => {
+            format!(":include={}", quote_arg(&path.to_string_lossy()))
         }
+        Op::Repo(repo, refname) => format!(":repo={}@{}", repo, refname),
+        Op::Authors(path) => {
+            format!(":authors={}", quote_arg(&path.to_string_lossy()))
+        }
+        Op::Message(template) => format!(":message={}", quote(template)),
+        Op::StripMessage(regex) => format!(":strip_message={}", quote(regex)),
 
         Op::Chain(a, b) => match (to_op(*a), to_op(*b)) {
             (Op::Subdir(p1), Op::Prefix(p2)) if p1 == p2 => {
-                format!("::{}/", p1.to_string_lossy())
+                format!("::{}", quote_arg(&format!("{}/", p1.to_string_lossy())))
             }
             (a, b) => format!("{}{}", spec2(&a), spec2(&b)),
         },
@@ -163,11 +275,48 @@ fn spec2(op: &Op) -> String {
         Op::Empty => ":empty".to_string(),
         Op::Paths => ":PATHS".to_string(),
         Op::Fold => ":FOLD".to_string(),
-        Op::Squash => ":SQUASH".to_string(),
-        Op::Subdir(path) => format!(":/{}", path.to_string_lossy()),
-        Op::File(path) => format!("::{}", path.to_string_lossy()),
-        Op::Prefix(path) => format!(":prefix={}", path.to_string_lossy()),
-        Op::Glob(pattern) => format!("::{}", pattern),
+        Op::Squash(None) => ":SQUASH".to_string(),
+        Op::Squash(Some(SquashBoundary::Commit(sha))) => format!(":SQUASH={}", sha),
+        Op::Squash(Some(SquashBoundary::Time(time))) => {
+            let date = chrono::NaiveDateTime::from_timestamp(*time, 0);
+            if *time % 86400 == 0 {
+                format!(":SQUASH={}", date.format("%Y-%m-%d"))
+            } else {
+                format!(":SQUASH=\"{}\"", date.format("%Y-%m-%dT%H:%M:%SZ"))
+            }
+        }
+        Op::Linear => ":linear".to_string(),
+        Op::Subdir(path) => format!(":/{}", quote_arg(&path.to_string_lossy())),
+        Op::File(dst, src) if dst == src => format!("::{}", quote_arg(&src.to_string_lossy())),
+        Op::File(dst, src) => format!(
+            "::{}={}",
+            quote_arg(&dst.to_string_lossy()),
+            quote_arg(&src.to_string_lossy())
+        ),
+        Op::Prefix(path) => format!(":prefix={}", quote_arg(&path.to_string_lossy())),
+        Op::Glob(pattern) => format!("::{}", quote_arg(pattern)),
+        Op::Regex(regex) => format!(":regex={}", quote(regex)),
+        Op::Attr(attr) => format!(":attr={}", attr),
+        Op::Submodules => ":submodules".to_string(),
+        Op::MaxSize(size, false) => format!(":maxsize={}", size),
+        Op::MaxSize(size, true) => format!(":maxsize={},stub", size),
+        Op::Patterns(patterns) => format!(
+            ":patterns={}",
+            patterns
+                .iter()
+                .map(|x| quote(x))
+                .collect::<Vec<_>>()
+                .join(",")
+        ),
+        Op::PatternsFile(path) => format!(":patterns_file={}", quote_arg(&path.to_string_lossy())),
+        Op::Replace(pattern, regex, replacement) => {
+            format!(
+                ":replace={},{},{}",
+                quote(pattern),
+                quote(regex),
+                quote(replacement)
+            )
+        }
     }
 }
 
@@ -178,7 +327,7 @@ pub fn src_path(filter: Filter) -> std::path::PathBuf {
 fn src_path2(op: &Op) -> std::path::PathBuf {
     normalize_path(&match op {
         Op::Subdir(path) => path.to_owned(),
-        Op::File(path) => path.to_owned(),
+        Op::File(_, src) => src.to_owned(),
         Op::Chain(a, b) => src_path(*a).join(src_path(*b)),
         _ => std::path::PathBuf::new(),
     })
@@ -192,13 +341,251 @@ pub fn dst_path(filter: Filter) -> std::path::PathBuf {
 fn dst_path2(op: &Op) -> std::path::PathBuf {
     normalize_path(&match op {
         Op::Prefix(path) => path.to_owned(),
-        Op::File(path) => path.to_owned(),
+        Op::File(dst, _) => dst.to_owned(),
         Op::Chain(a, b) => dst_path(*b).join(dst_path(*a)),
         _ => std::path::PathBuf::new(),
     })
     .to_owned()
 }
 
+/// Glob patterns matching all paths of the input that can influence the result of
+/// the filter, like `src_path` but for filters selecting more than one directory.
+/// A pattern matching a directory includes everything inside of it, `**` means the
+/// whole tree. Filters that depend on file content, like `:workspace=`, are assumed
+/// to read the whole tree.
+pub fn footprint(filter: Filter) -> Vec<String> {
+    let mut patterns = footprint2(&to_op(filter), vec!["**".to_string()]);
+    patterns.sort();
+    patterns.dedup();
+    if patterns.iter().any(|x| x == "**") {
+        return vec!["**".to_string()];
+    }
+    // Drop paths inside of directories that are already part of the footprint
+    let covered = |p: &String| {
+        patterns
+            .iter()
+            .any(|q| q != p && !is_glob(q) && covers(q, Path::new(p)))
+    };
+    return patterns.iter().filter(|p| !covered(p)).cloned().collect();
+}
+
+/// Check if `path` is matched by one of the patterns returned by `footprint`
+pub fn footprint_matches(patterns: &[String], path: &Path) -> bool {
+    patterns.iter().any(|p| covers(p, path))
+}
+
+/// Paths of the input that are read when the paths matched by `read` are read from the
+/// output of `op`.
+fn footprint2(op: &Op, read: Vec<String>) -> Vec<String> {
+    if read.is_empty() {
+        return read;
+    }
+    let all = || vec!["**".to_string()];
+    match op {
+        Op::Empty | Op::Repo(..) => vec![],
+
+        Op::Nop
+        | Op::Fold
+        | Op::Squash(_)
+        | Op::Linear
+        | Op::Paths
+        | Op::Message(_)
+        | Op::StripMessage(_)
+        | Op::Submodules
+        | Op::Replace(..)
+        | Op::MaxSize(..) => read,
+
+        Op::Authors(path) => {
+            let mut read = read;
+            read.push(path.to_string_lossy().to_string());
+            read
+        }
+
+        Op::Workspace(_) | Op::Include(_) | Op::PatternsFile(_) | Op::Attr(_) => all(),
+
+        Op::Glob(pattern) => narrow(read, vec![pattern.clone()]),
+        Op::Regex(_) => read,
+        Op::Patterns(patterns) => narrow(
+            read,
+            patterns
+                .iter()
+                .filter(|p| !p.starts_with('!'))
+                .map(|p| {
+                    let p = p.trim_end_matches('/');
+                    if p.contains('/') {
+                        p.trim_start_matches('/').to_string()
+                    } else {
+                        format!("**/{}", p)
+                    }
+                })
+                .collect(),
+        ),
+
+        Op::Subdir(path) => read
+            .iter()
+            .map(|x| {
+                if x == "**" {
+                    path.to_string_lossy().to_string()
+                } else {
+                    path.join(x).to_string_lossy().to_string()
+                }
+            })
+            .collect(),
+        Op::Prefix(path) => read
+            .iter()
+            .filter_map(|x| {
+                if covers(x, path) || is_glob(x) {
+                    Some("**".to_string())
+                } else {
+                    Path::new(x)
+                        .strip_prefix(path)
+                        .ok()
+                        .map(|x| x.to_string_lossy().to_string())
+                }
+            })
+            .collect(),
+        Op::File(dst, src) => {
+            if read.iter().any(|x| covers(x, dst)) {
+                vec![src.to_string_lossy().to_string()]
+            } else {
+                vec![]
+            }
+        }
+
+        Op::Chain(a, b) => footprint2(&to_op(*a), footprint2(&to_op(*b), read)),
+        Op::Compose(filters) => filters
+            .iter()
+            .flat_map(|f| footprint2(&to_op(*f), read.clone()))
+            .collect(),
+        Op::Subtract(a, b) => {
+            let mut result = footprint2(&to_op(*a), read.clone());
+            result.extend(footprint2(&to_op(*b), read));
+            result
+        }
+    }
+}
+
+/// Restrict `read` to the paths selected by `patterns`
+fn narrow(read: Vec<String>, patterns: Vec<String>) -> Vec<String> {
+    if read.iter().any(|x| x == "**") {
+        patterns
+    } else {
+        read
+    }
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(|c| c == '*' || c == '?' || c == '[')
+}
+
+/// Check if `pattern` matches `path` or one of its parent directories
+fn covers(pattern: &str, path: &Path) -> bool {
+    if pattern == "**" {
+        return true;
+    }
+    let pattern = ok_or!(glob::Pattern::new(pattern), {
+        return true;
+    });
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    path.ancestors()
+        .filter(|p| *p != Path::new(""))
+        .any(|p| pattern.matches_path_with(p, options))
+}
+
+/// Files that differ between the results of two filters applied to the same input
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct FilterDiff {
+    /// Only present in the result of the first filter
+    pub removed: Vec<std::path::PathBuf>,
+    /// Only present in the result of the second filter
+    pub added: Vec<std::path::PathBuf>,
+    /// Present in both results, but with different content or mode
+    pub changed: Vec<std::path::PathBuf>,
+}
+
+impl FilterDiff {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare the trees produced by `a` and `b` from `tree`.
+/// Filters that optimize to the same filter are equivalent without looking at the tree.
+pub fn diff(
+    transaction: &cache::Transaction,
+    a: Filter,
+    b: Filter,
+    tree: git2::Tree,
+) -> JoshResult<FilterDiff> {
+    if opt::optimize(a) == opt::optimize(b) {
+        return Ok(FilterDiff::default());
+    }
+    let repo = transaction.repo();
+    let tree_a = apply(transaction, a, tree.clone())?;
+    let tree_b = apply(transaction, b, tree)?;
+    if tree_a.id() == tree_b.id() {
+        return Ok(FilterDiff::default());
+    }
+
+    let mut changed = vec![];
+    for path in blob_paths(&tree_a)? {
+        let (ea, eb) = (
+            tree_a.get_path(&path)?,
+            ok_or!(tree_b.get_path(&path), { continue }),
+        );
+        if (ea.id(), ea.filemode()) != (eb.id(), eb.filemode()) {
+            changed.push(path);
+        }
+    }
+
+    Ok(FilterDiff {
+        removed: blob_paths(&repo.find_tree(tree::subtract(repo, tree_a.id(), tree_b.id())?)?)?,
+        added: blob_paths(&repo.find_tree(tree::subtract(repo, tree_b.id(), tree_a.id())?)?)?,
+        changed,
+    })
+}
+
+/// Like `diff`, but compare the results for `commit` and all of its ancestors.
+/// Returns the commits where the results differ, newest first.
+/// Only trees are compared, differences in history or metadata caused by filters
+/// like `:SQUASH` or `:authors=` are not detected.
+pub fn diff_history(
+    transaction: &cache::Transaction,
+    a: Filter,
+    b: Filter,
+    commit: git2::Oid,
+) -> JoshResult<Vec<(git2::Oid, FilterDiff)>> {
+    let repo = transaction.repo();
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(git2::Sort::TOPOLOGICAL)?;
+    walk.push(commit)?;
+
+    let mut result = vec![];
+    for id in walk {
+        let id = id?;
+        let d = diff(transaction, a, b, repo.find_commit(id)?.tree()?)?;
+        if !d.is_empty() {
+            result.push((id, d));
+        }
+    }
+    return Ok(result);
+}
+
+fn blob_paths(tree: &git2::Tree) -> JoshResult<Vec<std::path::PathBuf>> {
+    let mut paths = vec![];
+    tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() != Some(git2::ObjectType::Tree) {
+            paths.push(Path::new(root).join(entry.name().unwrap_or("")));
+        }
+        0
+    })?;
+    return Ok(paths);
+}
+
 /// Calculate the filtered commit for `commit`. This can take some time if done
 /// for the first time and thus should generally be done asynchronously.
 pub fn apply_to_commit(
@@ -221,6 +608,302 @@ pub fn apply_to_commit(
     Err(josh_error("apply_to_commit did not finish"))
 }
 
+/// Apply a filter that depends on files in the tree of each commit, like `:workspace`.
+/// `current` returns the filter selected by a tree, which is applied together with `base`.
+/// When it changes, the history of newly selected paths is added with extra parents.
+fn apply_to_commit_dynamic(
+    filter: Filter,
+    base: Filter,
+    commit: &git2::Commit,
+    transaction: &cache::Transaction,
+    current: &dyn Fn(&git2::Tree) -> JoshResult<Filter>,
+) -> JoshResult<Option<git2::Oid>> {
+    let repo = transaction.repo();
+    let normal_parents = commit
+        .parent_ids()
+        .map(|parent| transaction.get(filter, parent))
+        .collect::<Option<Vec<git2::Oid>>>();
+
+    let normal_parents = some_or!(normal_parents, { return Ok(None) });
+
+    let cw = current(&commit.tree()?)?;
+
+    let extra_parents = commit
+        .parents()
+        .map(|parent| {
+            rs_tracing::trace_scoped!("parent", "id": parent.id().to_string());
+            let pcw = current(&parent.tree().unwrap_or(tree::empty(&repo)))?;
+
+            apply_to_commit2(&Op::Subtract(cw, pcw), &parent, transaction)
+        })
+        .collect::<JoshResult<Option<Vec<_>>>>()?;
+
+    let extra_parents = some_or!(extra_parents, { return Ok(None) });
+
+    let (filtered_tree, repo_parents) = if repo_name(cw).is_some() {
+        // The content of other repositories depends on the commit, not only on the tree
+        let filters = match to_op(opt::flatten(compose(base, cw))) {
+            Op::Compose(filters) => filters,
+            _ => vec![compose(base, cw)],
+        };
+        some_or!(apply_compose_to_commit(&filters, commit, transaction)?, {
+            return Ok(None);
+        })
+    } else {
+        (apply(transaction, filter, commit.tree()?)?, vec![])
+    };
+
+    let filtered_parent_ids = normal_parents
+        .into_iter()
+        .chain(extra_parents.into_iter())
+        .chain(repo_parents.into_iter())
+        .collect();
+
+    return Some(history::create_filtered_commit(
+        commit,
+        filtered_parent_ids,
+        filtered_tree,
+        transaction,
+        filter,
+        None,
+    ))
+    .transpose();
+}
+
+/// Apply each of `filters` to `commit` and compose the resulting trees.
+/// Also returns the filtered commits of members using other repositories whose
+/// content changed compared to the parents of `commit`, to be used as extra parents.
+fn apply_compose_to_commit<'a>(
+    filters: &Vec<Filter>,
+    commit: &git2::Commit,
+    transaction: &'a cache::Transaction,
+) -> JoshResult<Option<(git2::Tree<'a>, Vec<git2::Oid>)>> {
+    let repo = transaction.repo();
+    let filtered = filters
+        .iter()
+        .map(|f| apply_to_commit2(&to_op(*f), &commit, transaction))
+        .collect::<JoshResult<Option<Vec<_>>>>()?;
+
+    let filtered = some_or!(filtered, { return Ok(None) });
+
+    let mut repo_parents = vec![];
+    for (f, id) in filters.iter().zip(filtered.iter()) {
+        if *id == git2::Oid::zero() || repo_name(*f).is_none() || repo_parents.contains(id) {
+            continue;
+        }
+        let parent_ids = commit
+            .parents()
+            .map(|p| apply_to_commit2(&to_op(*f), &p, transaction))
+            .collect::<JoshResult<Option<Vec<_>>>>()?;
+        let parent_ids = some_or!(parent_ids, { return Ok(None) });
+        if !parent_ids.contains(id) {
+            repo_parents.push(*id);
+        }
+    }
+
+    let filtered = filters
+        .iter()
+        .zip(filtered.into_iter())
+        .filter(|(_, id)| *id != git2::Oid::zero())
+        .into_iter()
+        .map(|(f, id)| Ok((f, repo.find_commit(id)?.tree()?)))
+        .collect::<JoshResult<Vec<_>>>()?;
+
+    Ok(Some((tree::compose(&transaction, filtered)?, repo_parents)))
+}
+
+/// Names of all other repositories used by the filter when it is applied to `tree`,
+/// including the ones used by workspace and included files in `tree`. Other versions
+/// of those files can use different repositories.
+pub fn repo_names(
+    transaction: &cache::Transaction,
+    filter: Filter,
+    tree: git2::Tree,
+) -> JoshResult<Vec<String>> {
+    let repo = transaction.repo();
+    let mut names = match to_op(filter) {
+        Op::Repo(name, _) => vec![name],
+        Op::Chain(a, b) => {
+            let mut names = repo_names(transaction, a, tree.clone())?;
+            names.extend(repo_names(transaction, b, apply(transaction, a, tree)?)?);
+            names
+        }
+        Op::Subtract(a, b) => {
+            let mut names = repo_names(transaction, a, tree.clone())?;
+            names.extend(repo_names(transaction, b, tree)?);
+            names
+        }
+        Op::Compose(filters) => {
+            let mut names = vec![];
+            for f in filters {
+                names.extend(repo_names(transaction, f, tree.clone())?);
+            }
+            names
+        }
+        Op::Workspace(path) => {
+            match parse::parse(&tree::get_blob(&repo, &tree, &path.join("workspace.josh"))) {
+                Ok(cw) => repo_names(transaction, cw, tree)?,
+                Err(_) => vec![],
+            }
+        }
+        Op::Include(_) => {
+            let resolved = resolve_includes(&repo, &tree, filter, &mut vec![])?;
+            repo_names(transaction, resolved, tree)?
+        }
+        _ => vec![],
+    };
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+/// Name of the first other repository used by the filter, if any
+fn repo_name(filter: Filter) -> Option<String> {
+    match to_op(filter) {
+        Op::Repo(name, _) => Some(name),
+        Op::Chain(a, b) | Op::Subtract(a, b) => repo_name(a).or_else(|| repo_name(b)),
+        Op::Compose(filters) => filters.into_iter().find_map(repo_name),
+        _ => None,
+    }
+}
+
+/// Check if the filter squashes history, so filtered root commits can stand for
+/// commits that have parents in the unfiltered history
+pub fn contains_squash(filter: Filter) -> bool {
+    match to_op(filter) {
+        Op::Squash(_) => true,
+        Op::Chain(a, b) | Op::Subtract(a, b) => contains_squash(a) || contains_squash(b),
+        Op::Compose(filters) => filters.into_iter().any(contains_squash),
+        _ => false,
+    }
+}
+
+/// Reject changes between the filtered trees `old` and `new` to paths that contain
+/// content of other repositories, as pushing to several repositories at once is not
+/// supported. `upstream` is used to resolve includes.
+pub fn check_repo_changes(
+    repo: &git2::Repository,
+    filter: Filter,
+    old: &git2::Tree,
+    new: &git2::Tree,
+    upstream: &git2::Tree,
+) -> JoshResult<()> {
+    let filter = match to_op(filter) {
+        Op::Workspace(_) => parse(&tree::get_blob(repo, new, &Path::new("workspace.josh")))
+            .unwrap_or(to_filter(Op::Empty)),
+        _ => filter,
+    };
+    let filter = opt::flatten(resolve_includes(repo, upstream, filter, &mut vec![])?);
+    let filters = match to_op(filter) {
+        Op::Compose(filters) => filters,
+        _ => vec![filter],
+    };
+
+    for f in filters {
+        let name = some_or!(repo_name(f), { continue });
+        let path = dst_path(f);
+        let (old_id, new_id) = if path == Path::new("") {
+            (Some(old.id()), Some(new.id()))
+        } else {
+            (
+                old.get_path(&path).map(|x| x.id()).ok(),
+                new.get_path(&path).map(|x| x.id()).ok(),
+            )
+        };
+        if old_id != new_id {
+            return Err(josh_error(&format!(
+                "can't push changes to {:?}: it contains content of {}",
+                path, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn repo_ref(name: &str, refname: &str) -> String {
+    format!("refs/josh/upstream/{}/{}", to_ns(name), refname)
+}
+
+/// Find the commit of the repository `name` that is used for `commit`: The last
+/// commit on the first parent history of `refname` that is not newer than `commit`.
+/// A commit counts as newer if it or any of its ancestors has a later commit time,
+/// so that the result does not change when more commits are added.
+fn repo_commit(
+    transaction: &cache::Transaction,
+    name: &str,
+    refname: &str,
+    commit: &git2::Commit,
+) -> JoshResult<Option<git2::Oid>> {
+    let repo = transaction.repo();
+    let tip = ok_or!(repo.refname_to_id(&repo_ref(name, refname)), {
+        return Ok(None);
+    });
+
+    let chain = if let Some(chain) = transaction.get_first_parents(tip) {
+        chain
+    } else {
+        let mut walk = repo.revwalk()?;
+        walk.simplify_first_parent()?;
+        walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        walk.push(tip)?;
+
+        let mut chain = vec![];
+        let mut newest = i64::MIN;
+        for id in walk {
+            let id = id?;
+            newest = std::cmp::max(newest, repo.find_commit(id)?.time().seconds());
+            chain.push((newest, id));
+        }
+        let chain = std::sync::Arc::new(chain);
+        transaction.insert_first_parents(tip, chain.clone());
+        chain
+    };
+
+    let n = chain.partition_point(|(time, _)| *time <= commit.time().seconds());
+    return Ok(if n == 0 { None } else { Some(chain[n - 1].1) });
+}
+
+/// Replace all `:include` filters by the content of the included files found in `tree`.
+/// `stack` contains the files that are currently being included, to detect cycles.
+fn resolve_includes(
+    repo: &git2::Repository,
+    tree: &git2::Tree,
+    filter: Filter,
+    stack: &mut Vec<std::path::PathBuf>,
+) -> JoshResult<Filter> {
+    let mut resolve = |f| resolve_includes(repo, tree, f, stack);
+    Ok(match to_op(filter) {
+        Op::Include(path) => {
+            if stack.contains(&path) {
+                return Err(josh_error(&format!(
+                    "include cycle: {}",
+                    stack
+                        .iter()
+                        .chain(std::iter::once(&path))
+                        .map(|x| x.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join(" -> ")
+                )));
+            }
+            let included =
+                parse::parse(&tree::get_blob(repo, tree, &path)).unwrap_or(to_filter(Op::Empty));
+            stack.push(path);
+            let resolved = resolve_includes(repo, tree, included, stack)?;
+            stack.pop();
+            resolved
+        }
+        Op::Chain(a, b) => to_filter(Op::Chain(resolve(a)?, resolve(b)?)),
+        Op::Subtract(a, b) => to_filter(Op::Subtract(resolve(a)?, resolve(b)?)),
+        Op::Compose(filters) => to_filter(Op::Compose(
+            filters
+                .into_iter()
+                .map(resolve)
+                .collect::<JoshResult<_>>()?,
+        )),
+        _ => filter,
+    })
+}
+
 pub fn apply_to_commit3(
     filter: Filter,
     commit: &git2::Commit,
@@ -229,10 +912,40 @@ pub fn apply_to_commit3(
     Ok(apply_to_commit2(&to_op(filter), commit, transaction)?.is_some())
 }
 
+/// Check if the result of `filter` for `commit` only depends on objects that are present.
+/// Otherwise it will change once the missing objects are fetched, so it should not be
+/// remembered.
+pub fn is_complete(transaction: &cache::Transaction, filter: Filter, commit: git2::Oid) -> bool {
+    !transaction.is_incomplete(opt::optimize(filter), commit)
+}
+
 fn apply_to_commit2(
     op: &Op,
     commit: &git2::Commit,
     transaction: &cache::Transaction,
+) -> JoshResult<Option<git2::Oid>> {
+    let missing_submodules = transaction.missing_submodules();
+    let result = apply_to_commit_op(op, commit, transaction)?;
+
+    // Results are incomplete if submodule commits were missing while creating them or
+    // their parents
+    if result.is_some() {
+        let filter = opt::optimize(to_filter(op.clone()));
+        if transaction.missing_submodules() != missing_submodules
+            || commit
+                .parent_ids()
+                .any(|parent| transaction.is_incomplete(filter, parent))
+        {
+            transaction.mark_incomplete(filter, commit.id());
+        }
+    }
+    return Ok(result);
+}
+
+fn apply_to_commit_op(
+    op: &Op,
+    commit: &git2::Commit,
+    transaction: &cache::Transaction,
 ) -> JoshResult<Option<git2::Oid>> {
     let filter = opt::optimize(to_filter(op.clone()));
     let repo = transaction.repo();
@@ -240,6 +953,11 @@ fn apply_to_commit2(
     match &to_op(filter) {
         Op::Nop => return Ok(Some(commit.id())),
         Op::Empty => return Ok(Some(git2::Oid::zero())),
+        Op::Repo(name, refname) => {
+            return Ok(Some(
+                repo_commit(transaction, name, refname, commit)?.unwrap_or(git2::Oid::zero()),
+            ))
+        }
 
         Op::Chain(a, b) => {
             let r = some_or!(apply_to_commit2(&to_op(*a), &commit, transaction)?, {
@@ -251,14 +969,11 @@ fn apply_to_commit2(
                 return Ok(Some(git2::Oid::zero()));
             }
         }
-        Op::Squash => {
-            return Some(history::rewrite_commit(
-                &repo,
-                &commit,
-                &vec![],
-                &commit.tree()?,
-            ))
-            .transpose()
+        Op::Squash(None) => {
+            return Some(history::create_squashed_commit(&commit, transaction)).transpose()
+        }
+        Op::Squash(Some(boundary)) if is_squashed(transaction, boundary, commit)? => {
+            return Ok(Some(squashed_root(transaction, filter, commit)?));
         }
         _ => {
             if let Some(oid) = transaction.get(filter, commit.id()) {
@@ -269,70 +984,97 @@ fn apply_to_commit2(
 
     rs_tracing::trace_scoped!("apply_to_commit", "spec": spec(filter), "commit": commit.id().to_string());
 
+    let mut extra_parent_ids = vec![];
+
     let filtered_tree = match &to_op(filter) {
         Op::Compose(filters) => {
-            let filtered = filters
-                .iter()
-                .map(|f| apply_to_commit2(&to_op(*f), &commit, transaction))
-                .collect::<JoshResult<Option<Vec<_>>>>()?;
-
-            let filtered = some_or!(filtered, { return Ok(None) });
-
-            let filtered = filters
-                .iter()
-                .zip(filtered.into_iter())
-                .filter(|(_, id)| *id != git2::Oid::zero())
-                .into_iter()
-                .map(|(f, id)| Ok((f, repo.find_commit(id)?.tree()?)))
-                .collect::<JoshResult<Vec<_>>>()?;
-
-            tree::compose(&transaction, filtered)?
+            let (filtered_tree, repo_parents) =
+                some_or!(apply_compose_to_commit(filters, commit, transaction)?, {
+                    return Ok(None);
+                });
+            extra_parent_ids = repo_parents;
+            filtered_tree
         }
         Op::Workspace(ws_path) => {
-            let normal_parents = commit
-                .parent_ids()
-                .map(|parent| transaction.get(filter, parent))
-                .collect::<Option<Vec<git2::Oid>>>();
+            let base = to_filter(Op::Subdir(ws_path.to_owned()));
+            return apply_to_commit_dynamic(filter, base, commit, transaction, &|tree| {
+                let cw = parse::parse(&tree::get_blob(
+                    &repo,
+                    tree,
+                    &ws_path.join("workspace.josh"),
+                ))
+                .unwrap_or(to_filter(Op::Empty));
+                resolve_includes(&repo, tree, cw, &mut vec![])
+            });
+        }
+        Op::Include(_) => {
+            let base = to_filter(Op::Empty);
+            return apply_to_commit_dynamic(filter, base, commit, transaction, &|tree| {
+                resolve_includes(&repo, tree, filter, &mut vec![])
+            });
+        }
+        Op::Authors(_) | Op::Message(_) | Op::StripMessage(_) => {
+            let filtered_parent_ids = commit
+                .parents()
+                .map(|x| transaction.get(filter, x.id()))
+                .collect::<Option<_>>();
 
-            let normal_parents = some_or!(normal_parents, { return Ok(None) });
+            let filtered_parent_ids = some_or!(filtered_parent_ids, { return Ok(None) });
 
-            let cw = parse::parse(&tree::get_blob(
-                &repo,
-                &commit.tree()?,
-                &ws_path.join("workspace.josh"),
+            return Some(history::create_filtered_commit(
+                commit,
+                filtered_parent_ids,
+                commit.tree()?,
+                transaction,
+                filter,
+                Some(apply_meta(&repo, &to_op(filter), commit)?),
             ))
-            .unwrap_or(to_filter(Op::Empty));
-
-            let extra_parents = commit
+            .transpose();
+        }
+        Op::Squash(Some(boundary)) => {
+            // Parents before the boundary are squashed right away, so their history
+            // does not need to be walked
+            let filtered_parent_ids = commit
                 .parents()
-                .map(|parent| {
-                    rs_tracing::trace_scoped!("parent", "id": parent.id().to_string());
-                    let pcw = parse::parse(&tree::get_blob(
-                        &repo,
-                        &parent.tree().unwrap_or(tree::empty(&repo)),
-                        &ws_path.join("workspace.josh"),
-                    ))
-                    .unwrap_or(to_filter(Op::Empty));
-
-                    apply_to_commit2(&Op::Subtract(cw, pcw), &parent, transaction)
+                .map(|x| {
+                    Ok(if is_squashed(transaction, boundary, &x)? {
+                        Some(squashed_root(transaction, filter, &x)?)
+                    } else {
+                        transaction.get(filter, x.id())
+                    })
                 })
-                .collect::<JoshResult<Option<Vec<_>>>>()?;
+                .collect::<JoshResult<Vec<_>>>()?
+                .into_iter()
+                .collect::<Option<_>>();
 
-            let extra_parents = some_or!(extra_parents, { return Ok(None) });
+            let filtered_parent_ids = some_or!(filtered_parent_ids, { return Ok(None) });
 
-            let filtered_parent_ids = normal_parents
-                .into_iter()
-                .chain(extra_parents.into_iter())
-                .collect();
+            return Some(history::create_filtered_commit(
+                commit,
+                filtered_parent_ids,
+                commit.tree()?,
+                transaction,
+                filter,
+                None,
+            ))
+            .transpose();
+        }
+        Op::Linear => {
+            let filtered_parent_ids = commit
+                .parent_ids()
+                .take(1)
+                .map(|x| transaction.get(filter, x))
+                .collect::<Option<_>>();
 
-            let filtered_tree = apply(transaction, filter, commit.tree()?)?;
+            let filtered_parent_ids = some_or!(filtered_parent_ids, { return Ok(None) });
 
             return Some(history::create_filtered_commit(
                 commit,
                 filtered_parent_ids,
-                filtered_tree,
+                commit.tree()?,
                 transaction,
                 filter,
+                None,
             ))
             .transpose();
         }
@@ -392,19 +1134,144 @@ fn apply_to_commit2(
         commit
             .parents()
             .map(|x| transaction.get(filter, x.id()))
-            .collect::<Option<_>>()
+            .collect::<Option<Vec<_>>>()
     };
 
-    let filtered_parent_ids = some_or!(filtered_parent_ids, { return Ok(None) });
+    let filtered_parent_ids = some_or!(filtered_parent_ids, { return Ok(None) })
+        .into_iter()
+        .chain(extra_parent_ids.into_iter())
+        .collect();
+
+    return Some(history::create_filtered_commit(
+        commit,
+        filtered_parent_ids,
+        filtered_tree,
+        transaction,
+        filter,
+        None,
+    ))
+    .transpose();
+}
+
+/// The root commit that `commit` and its history are squashed into by `filter`.
+/// Those are only created for parents of kept commits and for squashed commits filtered directly.
+fn squashed_root(
+    transaction: &cache::Transaction,
+    filter: Filter,
+    commit: &git2::Commit,
+) -> JoshResult<git2::Oid> {
+    if transaction.known(filter, commit.id()) {
+        if let Some(oid) = transaction.get(filter, commit.id()) {
+            return Ok(oid);
+        }
+    }
+    let squashed = history::create_squashed_commit(&commit, transaction)?;
+    transaction.insert(filter, commit.id(), squashed, true);
+    Ok(squashed)
+}
+
+/// Check if `commit` is squashed by a `:SQUASH=<boundary>` that `filter` starts with.
+/// Those commits and their ancestors don't need to be walked when filtering the history.
+pub fn is_squashed_by(
+    transaction: &cache::Transaction,
+    filter: Filter,
+    commit: git2::Oid,
+) -> JoshResult<bool> {
+    match to_op(filter) {
+        Op::Squash(Some(boundary)) => is_squashed(
+            transaction,
+            &boundary,
+            &transaction.repo().find_commit(commit)?,
+        ),
+        Op::Chain(a, _) => is_squashed_by(transaction, a, commit),
+        _ => Ok(false),
+    }
+}
+
+/// Check if `commit` is before `boundary` and thus squashed by `:SQUASH=...`.
+/// A time boundary is compared with the commit time of each commit on its own, so with
+/// non-monotonic commit times a squashed commit can still have kept ancestors.
+/// If the boundary commit is not found nothing is squashed.
+fn is_squashed(
+    transaction: &cache::Transaction,
+    boundary: &SquashBoundary,
+    commit: &git2::Commit,
+) -> JoshResult<bool> {
+    Ok(match boundary {
+        SquashBoundary::Time(time) => commit.time().seconds() < *time,
+        SquashBoundary::Commit(sha) => {
+            let boundary = git2::Oid::from_str(sha)?;
+            let ancestors = if let Some(ancestors) = transaction.get_ancestors(boundary) {
+                ancestors
+            } else {
+                let repo = transaction.repo();
+                let mut ancestors = std::collections::HashSet::new();
+                if repo.find_commit(boundary).is_ok() {
+                    let mut walk = repo.revwalk()?;
+                    walk.push(boundary)?;
+                    for id in walk {
+                        ancestors.insert(id?);
+                    }
+                }
+                let ancestors = std::sync::Arc::new(ancestors);
+                transaction.insert_ancestors(boundary, ancestors.clone());
+                ancestors
+            };
+            ancestors.contains(&commit.id())
+        }
+    })
+}
+
+/// Calculate the metadata of the filtered commit for filters that only change commit metadata
+fn apply_meta(
+    repo: &git2::Repository,
+    op: &Op,
+    commit: &git2::Commit,
+) -> JoshResult<history::CommitMeta> {
+    let mut meta = history::CommitMeta::from_commit(commit);
+    match op {
+        Op::Authors(path) => {
+            let mailmap = mailmap::Mailmap::parse(&tree::get_blob(&repo, &commit.tree()?, &path));
+            if let Some(author) = mailmap.map(&meta.author)? {
+                meta.author = author;
+            }
+            if let Some(committer) = mailmap.map(&meta.committer)? {
+                meta.committer = committer;
+            }
+        }
+        Op::Message(template) => {
+            let (subject, body) = split_message(&meta.message);
+            let vars: std::collections::HashMap<String, &str> = [
+                ("message".to_string(), meta.message.as_str()),
+                ("subject".to_string(), subject),
+                ("body".to_string(), body),
+            ]
+            .iter()
+            .cloned()
+            .collect();
+            meta.message = strfmt::strfmt(&template, &vars)?;
+            if !meta.message.ends_with("\n") {
+                meta.message.push('\n');
+            }
+        }
+        Op::StripMessage(regex) => {
+            let regex = regex::RegexBuilder::new(&regex).multi_line(true).build()?;
+            let stripped = regex.replace_all(&meta.message, "");
+            meta.message = format!("{}\n", stripped.trim_end());
+        }
+        _ => {}
+    }
+    Ok(meta)
+}
 
-    return Some(history::create_filtered_commit(
-        commit,
-        filtered_parent_ids,
-        filtered_tree,
-        transaction,
-        filter,
-    ))
-    .transpose();
+/// Split a commit message into subject and body
+fn split_message(message: &str) -> (&str, &str) {
+    let message = message.trim_start_matches("\n");
+    if let Some(i) = message.find("\n\n") {
+        (&message[..i], message[i..].trim_start_matches("\n"))
+    } else {
+        (message.trim_end_matches("\n"), "")
+    }
 }
 
 /// Filter a single tree. This does not involve walking history and is thus fast in most cases.
@@ -426,7 +1293,11 @@ fn apply2<'a>(
         Op::Nop => return Ok(tree),
         Op::Empty => return Ok(tree::empty(&repo)),
         Op::Fold => return Ok(tree),
-        Op::Squash => return Ok(tree),
+        Op::Squash(_) => return Ok(tree),
+        Op::Linear => return Ok(tree),
+        Op::Authors(_) => return Ok(tree),
+        Op::Message(_) => return Ok(tree),
+        Op::StripMessage(_) => return Ok(tree),
 
         Op::Glob(pattern) => {
             let pattern = glob::Pattern::new(pattern)?;
@@ -443,13 +1314,87 @@ fn apply2<'a>(
                 to_filter(op.clone()).id(),
             )
         }
-        Op::File(path) => {
+        Op::Regex(regex) => {
+            let regex = path_regex(regex)?;
+            tree::remove_pred(
+                transaction,
+                "",
+                tree.id(),
+                &|path, isblob| isblob && regex.is_match(&path.to_string_lossy()),
+                to_filter(op.clone()).id(),
+            )
+        }
+        Op::Patterns(_) | Op::PatternsFile(_) => {
+            let (patterns, key) = pattern_list(&repo, op, &tree)?;
+            tree::remove_pred(
+                transaction,
+                "",
+                tree.id(),
+                &|path, isblob| patterns.matches(&path, !isblob),
+                key,
+            )
+        }
+        Op::Submodules => {
+            tree::inline_submodules(transaction, tree.id(), to_filter(op.clone()).id())
+        }
+        Op::Attr(attr) => tree::remove_attr(
+            transaction,
+            attr,
+            "",
+            tree.id(),
+            &attributes::Attributes::default(),
+            to_filter(op.clone()).id(),
+        ),
+        Op::MaxSize(max, stub) => tree::map_blobs(
+            transaction,
+            "",
+            tree.id(),
+            &|_, id| {
+                let size = blob_size(&repo, id)?;
+                Ok(if size <= *max {
+                    id
+                } else if *stub {
+                    repo.blob(size_stub(id, size).as_bytes())?
+                } else {
+                    git2::Oid::zero()
+                })
+            },
+            Some(to_filter(op.clone()).id()),
+        ),
+        Op::Replace(pattern, regex, replacement) => {
+            let pattern = glob::Pattern::new(pattern)?;
+            let options = glob::MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: true,
+                require_literal_leading_dot: true,
+            };
+            let regex = regex::bytes::Regex::new(regex)?;
+            tree::map_blobs(
+                transaction,
+                "",
+                tree.id(),
+                &|path, id| {
+                    if !pattern.matches_path_with(&path, options) {
+                        return Ok(id);
+                    }
+                    let blob = repo.find_blob(id)?;
+                    Ok(
+                        match regex.replace_all(blob.content(), replacement.as_bytes()) {
+                            std::borrow::Cow::Borrowed(_) => id,
+                            std::borrow::Cow::Owned(replaced) => repo.blob(&replaced)?,
+                        },
+                    )
+                },
+                Some(to_filter(op.clone()).id()),
+            )
+        }
+        Op::File(dst, src) => {
             let (file, mode) = tree
-                .get_path(&path)
+                .get_path(&src)
                 .map(|x| (x.id(), x.filemode()))
                 .unwrap_or((git2::Oid::zero(), 0o0100644));
             if let Ok(_) = repo.find_blob(file) {
-                tree::insert(&repo, &tree::empty(&repo), &path, file, mode)
+                tree::insert(&repo, &tree::empty(&repo), &dst, file, mode)
             } else {
                 Ok(tree::empty(&repo))
             }
@@ -473,6 +1418,21 @@ fn apply2<'a>(
 
         Op::Paths => tree::pathstree("", tree.id(), transaction),
 
+        Op::Include(_) => {
+            let resolved = resolve_includes(&repo, &tree, to_filter(op.clone()), &mut vec![])?;
+            apply(transaction, resolved, tree)
+        }
+        Op::Repo(name, refname) => {
+            // Without a commit the latest state of the other repository is used
+            if let Ok(commit) = repo
+                .find_reference(&repo_ref(name, refname))
+                .and_then(|r| r.peel_to_commit())
+            {
+                Ok(commit.tree()?)
+            } else {
+                Ok(tree::empty(&repo))
+            }
+        }
         Op::Workspace(path) => {
             let base = to_filter(Op::Subdir(path.to_owned()));
             if let Ok(cw) =
@@ -500,14 +1460,28 @@ fn apply2<'a>(
 }
 
 /// Calculate a tree with minimal differences from `parent_tree`
-/// such that `apply(unapply(tree, parent_tree)) == tree`
+/// such that `apply(unapply(tree, parent_tree)) == tree`.
+/// This holds for every `tree` that can be produced by the filter, filters for
+/// which no such tree exists return a "filter not reversible" error instead.
 pub fn unapply<'a>(
     transaction: &'a cache::Transaction,
     filter: Filter,
     tree: git2::Tree<'a>,
     parent_tree: git2::Tree<'a>,
 ) -> JoshResult<git2::Tree<'a>> {
-    unapply2(transaction, &to_op(filter), tree, parent_tree)
+    unapply2(transaction, &to_op(filter), tree, parent_tree, None)
+}
+
+/// Same as `unapply`, but filters that need to create commits (like `:submodules`)
+/// use `meta` for them.
+pub fn unapply_for_commit<'a>(
+    transaction: &'a cache::Transaction,
+    filter: Filter,
+    tree: git2::Tree<'a>,
+    parent_tree: git2::Tree<'a>,
+    meta: &history::CommitMeta,
+) -> JoshResult<git2::Tree<'a>> {
+    unapply2(transaction, &to_op(filter), tree, parent_tree, Some(meta))
 }
 
 fn unapply2<'a>(
@@ -515,15 +1489,35 @@ fn unapply2<'a>(
     op: &Op,
     tree: git2::Tree<'a>,
     parent_tree: git2::Tree<'a>,
+    meta: Option<&history::CommitMeta>,
 ) -> JoshResult<git2::Tree<'a>> {
     return match op {
         Op::Nop => Ok(tree),
         Op::Empty => Ok(parent_tree),
+        Op::Linear => Ok(tree),
+        // The squashed commit has the same tree as the original one, commits on top of it
+        // are applied to the original commit found by `history::find_unapply_base`
+        Op::Squash(_) => Ok(tree),
+        Op::Authors(_) => Ok(tree),
+        Op::Message(_) => Ok(tree),
+        Op::StripMessage(_) => Ok(tree),
 
         Op::Chain(a, b) => {
             let p = apply(transaction, *a, parent_tree.clone())?;
-            let x = unapply(transaction, *b, tree, p)?;
-            unapply(transaction, *a, x, parent_tree)
+            let x = unapply2(transaction, &to_op(*b), tree, p, meta)?;
+            unapply2(transaction, &to_op(*a), x, parent_tree, meta)
+        }
+        // Content of other repositories is never changed, see `check_repo_changes`
+        Op::Repo(_, _) => Ok(parent_tree),
+        Op::Include(_) => {
+            // Included files are taken from upstream, they might not be part of the result
+            let resolved = resolve_includes(
+                &transaction.repo(),
+                &parent_tree,
+                to_filter(op.clone()),
+                &mut vec![],
+            )?;
+            unapply2(transaction, &to_op(resolved), tree, parent_tree, meta)
         }
         Op::Workspace(path) => {
             let root = to_filter(Op::Subdir(path.to_owned()));
@@ -536,7 +1530,7 @@ fn unapply2<'a>(
                     blob = format!("{}", c);
                 }
             }
-            let blob = &format!("{}{}\n", &blob, pretty(parsed, 0));
+            let blob = &format!("{}{}\n", &blob, pretty_workspace(mapped)?);
 
             let tree = tree::insert(
                 &transaction.repo(),
@@ -546,9 +1540,21 @@ fn unapply2<'a>(
                 0o0100644, // Should this handle filemode?
             )?;
 
-            return unapply(transaction, compose(root, parsed), tree, parent_tree);
+            return unapply2(
+                transaction,
+                &to_op(compose(root, parsed)),
+                tree,
+                parent_tree,
+                meta,
+            );
         }
         Op::Compose(filters) => {
+            // Includes need to be resolved with the upstream tree, because the members
+            // are also unapplied onto an empty tree
+            let filters = filters
+                .iter()
+                .map(|f| resolve_includes(&transaction.repo(), &parent_tree, *f, &mut vec![]))
+                .collect::<JoshResult<Vec<_>>>()?;
             let mut remaining = tree.clone();
             let mut result = parent_tree.clone();
 
@@ -562,7 +1568,7 @@ fn unapply2<'a>(
                 if tree::empty_id() == from_empty.id() {
                     continue;
                 }
-                result = unapply(transaction, *other, remaining.clone(), result)?;
+                result = unapply2(transaction, &to_op(*other), remaining.clone(), result, meta)?;
                 let reapply = apply(transaction, *other, from_empty.clone())?;
 
                 remaining = transaction.repo().find_tree(tree::subtract(
@@ -575,13 +1581,13 @@ fn unapply2<'a>(
             return Ok(result);
         }
 
-        Op::File(path) => {
+        Op::File(dst, src) => {
             let (file, mode) = tree
-                .get_path(&path)
+                .get_path(&dst)
                 .map(|x| (x.id(), x.filemode()))
                 .unwrap_or((git2::Oid::zero(), 0o0100644));
             if let Ok(_) = transaction.repo().find_blob(file) {
-                tree::insert(&transaction.repo(), &parent_tree, &path, file, mode)
+                tree::insert(&transaction.repo(), &parent_tree, &src, file, mode)
             } else {
                 Ok(tree::empty(&transaction.repo()))
             }
@@ -592,7 +1598,14 @@ fn unapply2<'a>(
                 let subtracted = tree::subtract(
                     &transaction.repo(),
                     tree.id(),
-                    unapply2(transaction, &b, tree, tree::empty(&transaction.repo()))?.id(),
+                    unapply2(
+                        transaction,
+                        &b,
+                        tree,
+                        tree::empty(&transaction.repo()),
+                        meta,
+                    )?
+                    .id(),
                 )?;
                 Ok(transaction.repo().find_tree(tree::overlay(
                     &transaction.repo(),
@@ -600,7 +1613,28 @@ fn unapply2<'a>(
                     subtracted,
                 )?)?)
             }
-            _ => return Err(josh_error("filter not reversible")),
+            (op_a, _) => {
+                // Restore the part of the output of `a` that was removed because it is
+                // selected by `b`, calculated the same way as in `apply2`
+                let repo = transaction.repo();
+                let af = apply(transaction, *a, parent_tree.clone())?;
+                let bf = apply(transaction, *b, parent_tree.clone())?;
+                let bu = unapply(transaction, *b, bf, tree::empty(&repo))?;
+                let ba = apply(transaction, *a, bu)?;
+                let kept = tree::subtract(&repo, af.id(), ba.id())?;
+                let removed = tree::subtract(&repo, af.id(), kept)?;
+
+                let full = repo.find_tree(tree::overlay(&repo, removed, tree.id())?)?;
+                let result = unapply2(transaction, &op_a, full, parent_tree, meta)?;
+
+                // Files added to paths selected by `b` would be removed again
+                if apply2(transaction, op, result.clone())?.id() != tree.id() {
+                    return Err(josh_error(
+                        "filter not reversible: changes to paths removed by :subtract",
+                    ));
+                }
+                Ok(result)
+            }
         },
         Op::Glob(pattern) => {
             let pattern = glob::Pattern::new(pattern)?;
@@ -622,6 +1656,157 @@ fn unapply2<'a>(
                 subtracted.id(),
             )?)?)
         }
+        Op::Regex(regex) => {
+            let regex = path_regex(regex)?;
+            let subtracted = tree::remove_pred(
+                transaction,
+                "",
+                tree.id(),
+                &|path, isblob| isblob && regex.is_match(&path.to_string_lossy()),
+                to_filter(op.clone()).id(),
+            )?;
+            Ok(transaction.repo().find_tree(tree::overlay(
+                &transaction.repo(),
+                parent_tree.id(),
+                subtracted.id(),
+            )?)?)
+        }
+        Op::Patterns(_) | Op::PatternsFile(_) => {
+            let repo = transaction.repo();
+            let (patterns, key) = pattern_list(&repo, op, &parent_tree)?;
+            let pred = |path: &std::path::Path, isblob: bool| patterns.matches(&path, !isblob);
+
+            let selected = tree::remove_pred(transaction, "", tree.id(), &pred, key)?;
+            let parent_selected = tree::remove_pred(transaction, "", parent_tree.id(), &pred, key)?;
+            let remaining = tree::subtract(&repo, parent_tree.id(), parent_selected.id())?;
+
+            Ok(repo.find_tree(tree::overlay(&repo, remaining, selected.id())?)?)
+        }
+        Op::Submodules => {
+            let repo = transaction.repo();
+            Ok(repo.find_tree(unapply_submodules(
+                transaction,
+                op,
+                tree.id(),
+                &parent_tree,
+                meta,
+            )?)?)
+        }
+        Op::Attr(_) => {
+            // Keep everything that is hidden by the filter
+            let repo = transaction.repo();
+            let visible = apply(transaction, to_filter(op.clone()), parent_tree.clone())?;
+            let hidden = tree::subtract(&repo, parent_tree.id(), visible.id())?;
+            Ok(repo.find_tree(tree::overlay(&repo, tree.id(), hidden)?)?)
+        }
+        Op::MaxSize(max, false) => {
+            check_max_size(&transaction.repo(), &tree, *max)?;
+
+            // Large files are not part of the filtered tree, so take them from upstream
+            let repo = transaction.repo();
+            let large = tree::map_blobs(
+                transaction,
+                "",
+                parent_tree.id(),
+                &|_, id| {
+                    Ok(if blob_size(&repo, id)? > *max {
+                        id
+                    } else {
+                        git2::Oid::zero()
+                    })
+                },
+                None,
+            )?;
+            Ok(repo.find_tree(tree::overlay(&repo, tree.id(), large.id())?)?)
+        }
+        Op::MaxSize(max, true) => {
+            check_max_size(&transaction.repo(), &tree, *max)?;
+
+            // Stubs that are unchanged are replaced by the original files
+            let repo = transaction.repo();
+            tree::map_blobs(
+                transaction,
+                "",
+                tree.id(),
+                &|path, id| {
+                    if let Ok(original) = parent_tree.get_path(&path) {
+                        let size = blob_size(&repo, original.id())?;
+                        if size > *max
+                            && id
+                                == git2::Oid::hash_object(
+                                    git2::ObjectType::Blob,
+                                    size_stub(original.id(), size).as_bytes(),
+                                )?
+                        {
+                            return Ok(original.id());
+                        }
+                    }
+                    Ok(id)
+                },
+                None,
+            )
+        }
+        Op::Replace(pattern, regex, replacement) => {
+            let repo = transaction.repo();
+            let pattern = glob::Pattern::new(pattern)?;
+            let options = glob::MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: true,
+                require_literal_leading_dot: true,
+            };
+            let forward = regex::bytes::Regex::new(regex)?;
+            let forward = |content: &[u8]| {
+                forward
+                    .replace_all(content, replacement.as_bytes())
+                    .into_owned()
+            };
+
+            // Replacing a literal string by another literal string can be reverted
+            let backward = match (literal(regex), replacement.contains('$')) {
+                (Some(original), false) => Some((
+                    regex::bytes::Regex::new(&regex::escape(replacement))?,
+                    original,
+                )),
+                _ => None,
+            };
+
+            tree::map_blobs(
+                transaction,
+                "",
+                tree.id(),
+                &|path, id| {
+                    if !pattern.matches_path_with(&path, options) {
+                        return Ok(id);
+                    }
+                    let content = repo.find_blob(id)?.content().to_vec();
+
+                    // Files that are unchanged keep their original content
+                    if let Ok(original) = parent_tree.get_path(&path) {
+                        if let Ok(blob) = repo.find_blob(original.id()) {
+                            if forward(blob.content()) == content {
+                                return Ok(original.id());
+                            }
+                        }
+                    }
+                    if let Some((regex, original)) = &backward {
+                        let candidate = regex
+                            .replace_all(&content, regex::bytes::NoExpand(original.as_bytes()))
+                            .into_owned();
+                        if forward(&candidate) == content {
+                            return Ok(repo.blob(&candidate)?);
+                        }
+                    }
+                    if forward(&content) == content {
+                        return Ok(id);
+                    }
+                    Err(josh_error(&format!(
+                        "filter not reversible: {}",
+                        path.to_string_lossy()
+                    )))
+                },
+                None,
+            )
+        }
         Op::Prefix(path) => Ok(tree
             .get_path(&path)
             .and_then(|x| transaction.repo().find_tree(x.id()))
@@ -637,6 +1822,225 @@ fn unapply2<'a>(
     };
 }
 
+/// Calculate the metadata a commit with metadata `meta` in the filtered history should have
+/// when applied on top of `parent_tree`. This reverts the changes done by metadata rewriting
+/// filters like `:authors=` as far as possible.
+pub fn unapply_meta<'a>(
+    transaction: &'a cache::Transaction,
+    filter: Filter,
+    meta: history::CommitMeta,
+    parent_tree: git2::Tree<'a>,
+) -> JoshResult<history::CommitMeta> {
+    unapply_meta2(transaction, &to_op(filter), meta, parent_tree)
+}
+
+fn unapply_meta2<'a>(
+    transaction: &'a cache::Transaction,
+    op: &Op,
+    meta: history::CommitMeta,
+    parent_tree: git2::Tree<'a>,
+) -> JoshResult<history::CommitMeta> {
+    let repo = transaction.repo();
+    return match op {
+        Op::Authors(path) => {
+            let mailmap = mailmap::Mailmap::parse(&tree::get_blob(&repo, &parent_tree, &path));
+            let mut meta = meta;
+            if let Some(author) = mailmap.unmap(&meta.author)? {
+                meta.author = author;
+            }
+            if let Some(committer) = mailmap.unmap(&meta.committer)? {
+                meta.committer = committer;
+            }
+            Ok(meta)
+        }
+        Op::Message(template) => {
+            // Only templates that add a fixed prefix and/or suffix can be reverted
+            let parts: Vec<_> = template.split("{message}").collect();
+            let mut meta = meta;
+            if let [prefix, suffix] = parts.as_slice() {
+                let suffix = &if suffix.ends_with("\n") || suffix.is_empty() {
+                    suffix.to_string()
+                } else {
+                    format!("{}\n", suffix)
+                };
+                if !(prefix.contains(&['{', '}'][..]) || suffix.contains(&['{', '}'][..]))
+                    && meta.message.len() >= prefix.len() + suffix.len()
+                    && meta.message.starts_with(prefix)
+                    && meta.message.ends_with(suffix)
+                {
+                    meta.message = format!(
+                        "{}\n",
+                        meta.message[prefix.len()..meta.message.len() - suffix.len()].trim_end()
+                    );
+                }
+            }
+            Ok(meta)
+        }
+        Op::Chain(a, b) => {
+            let p = apply(transaction, *a, parent_tree.clone())?;
+            let meta = unapply_meta(transaction, *b, meta, p)?;
+            unapply_meta(transaction, *a, meta, parent_tree)
+        }
+        Op::Compose(filters) => {
+            let mut meta = meta;
+            for f in filters {
+                meta = unapply_meta(transaction, *f, meta, parent_tree.clone())?;
+            }
+            Ok(meta)
+        }
+        Op::Include(_) => {
+            let resolved =
+                resolve_includes(&repo, &parent_tree, to_filter(op.clone()), &mut vec![])?;
+            unapply_meta(transaction, resolved, meta, parent_tree)
+        }
+        Op::Workspace(path) => {
+            let root = to_filter(Op::Subdir(path.to_owned()));
+            if let Ok(cw) = parse(&tree::get_blob(
+                &repo,
+                &parent_tree,
+                &path.join("workspace.josh"),
+            )) {
+                unapply_meta(transaction, compose(root, cw), meta, parent_tree)
+            } else {
+                Ok(meta)
+            }
+        }
+        _ => Ok(meta),
+    };
+}
+
+/// Turn directories of `tree` that are inlined submodules of `parent_tree` back into
+/// gitlinks. If the content of a submodule was changed a new commit is created for it.
+fn unapply_submodules(
+    transaction: &cache::Transaction,
+    op: &Op,
+    tree: git2::Oid,
+    parent_tree: &git2::Tree,
+    meta: Option<&history::CommitMeta>,
+) -> JoshResult<git2::Oid> {
+    let repo = transaction.repo();
+    let mut result = repo.find_tree(tree)?;
+    for entry in parent_tree.iter() {
+        let name = entry.name().ok_or(josh_error("no name"))?;
+        let (pushed, kind) = some_or!(result.get_name(name).map(|x| (x.id(), x.kind())), {
+            continue;
+        });
+        if kind != Some(git2::ObjectType::Tree) || pushed == entry.id() {
+            continue;
+        }
+
+        let id = match entry.kind() {
+            Some(git2::ObjectType::Tree) => {
+                unapply_submodules(transaction, op, pushed, &repo.find_tree(entry.id())?, meta)?
+            }
+            Some(git2::ObjectType::Commit) => {
+                let commit = ok_or!(repo.find_commit(entry.id()), { continue });
+                let inlined = apply(transaction, to_filter(op.clone()), commit.tree()?)?;
+                if inlined.id() == pushed {
+                    entry.id()
+                } else {
+                    let meta = meta.ok_or(josh_error(&format!(
+                        "can't create commit for submodule {:?}",
+                        name
+                    )))?;
+                    let new_tree = repo.find_tree(unapply_submodules(
+                        transaction,
+                        op,
+                        pushed,
+                        &commit.tree()?,
+                        Some(meta),
+                    )?)?;
+                    let id =
+                        history::rewrite_commit(&repo, &commit, &[&commit], &new_tree, Some(meta))?;
+                    transaction.insert_new_submodule_commit(id);
+                    id
+                }
+            }
+            _ => continue,
+        };
+
+        result = tree::insert(&repo, &result, &Path::new(name), id, entry.filemode())?;
+    }
+    return Ok(result.id());
+}
+
+fn blob_size(repo: &git2::Repository, id: git2::Oid) -> JoshResult<usize> {
+    Ok(repo.odb()?.read_header(id)?.0)
+}
+
+/// Files larger than `max` are never the result of `:maxsize`, so they can't be pushed
+fn check_max_size(repo: &git2::Repository, tree: &git2::Tree, max: usize) -> JoshResult<()> {
+    let mut large = vec![];
+    tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+        if entry.kind() == Some(git2::ObjectType::Blob)
+            && blob_size(repo, entry.id()).unwrap_or(0) > max
+        {
+            large.push(format!("{}{}", root, entry.name().unwrap_or("")));
+        }
+        git2::TreeWalkResult::Ok
+    })?;
+    if !large.is_empty() {
+        return Err(josh_error(&format!(
+            "filter not reversible: files larger than {} bytes: {}",
+            max,
+            large.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Content of the file replacing a blob that is too large for a `:maxsize=N,stub` filter
+fn size_stub(id: git2::Oid, size: usize) -> String {
+    format!("File removed by josh\nsize: {}\nblob: {}\n", size, id)
+}
+
+/// Compile the regex of a `:regex` filter so it only matches full paths
+fn path_regex(regex: &str) -> JoshResult<regex::Regex> {
+    regex::Regex::new(&format!("^(?:{})$", regex))
+        .map_err(|_| josh_error(&format!("invalid regex {:?}", regex)))
+}
+
+/// Get the pattern list for `:patterns` and `:patterns_file` filters, together with a
+/// key to cache results
+fn pattern_list(
+    repo: &git2::Repository,
+    op: &Op,
+    tree: &git2::Tree,
+) -> JoshResult<(patterns::PatternList, git2::Oid)> {
+    let key = to_filter(op.clone()).id();
+    match op {
+        Op::Patterns(patterns) => Ok((patterns::PatternList::parse(&patterns)?, key)),
+        Op::PatternsFile(path) => {
+            let content = tree::get_blob(&repo, &tree, &path);
+            let s = format!("{:?}", (key, &content));
+            Ok((
+                patterns::PatternList::from_file(&content)?,
+                git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes())?,
+            ))
+        }
+        _ => Err(josh_error("not a pattern list")),
+    }
+}
+
+/// The string matched by `regex` if it does only match a single literal string
+fn literal(regex: &str) -> Option<String> {
+    let mut result = String::new();
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(e) if e.is_ascii_punctuation() => result.push(e),
+                _ => return None,
+            },
+            '.' | '^' | '$' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|' => {
+                return None
+            }
+            c => result.push(c),
+        }
+    }
+    Some(result)
+}
+
 /// Create a filter that is the result of feeding the output of `first` into `second`
 pub fn chain(first: Filter, second: Filter) -> Filter {
     opt::optimize(to_filter(Op::Chain(first, second)))
@@ -710,6 +2114,7 @@ mod tests {
         assert_eq!(PathBuf::from("x"), src_path(parse(":/x").unwrap()));
         assert_eq!(PathBuf::from("x/y"), src_path(parse(":/x/y").unwrap()));
         assert_eq!(PathBuf::from("x/y"), src_path(parse(":/x::y").unwrap()));
+        assert_eq!(PathBuf::from("x/y"), src_path(parse(":/x::z=y").unwrap()));
     }
 
     #[test]
@@ -717,6 +2122,7 @@ mod tests {
         assert_eq!(PathBuf::from(""), dst_path(parse(":/x").unwrap()));
         assert_eq!(PathBuf::from(""), dst_path(parse(":/x/y").unwrap()));
         assert_eq!(PathBuf::from("y"), dst_path(parse(":/x::y").unwrap()));
+        assert_eq!(PathBuf::from("a/z"), dst_path(parse(":/x::a/z=y").unwrap()));
         assert_eq!(
             PathBuf::from("a/y"),
             dst_path(parse(":[a=:/x::y/]").unwrap())
@@ -727,4 +2133,178 @@ mod tests {
             dst_path(parse(":[a=:/x::y/,a/b=:/i]:prefix=c").unwrap())
         );
     }
+
+    #[test]
+    fn parse_error_test() {
+        let e = try_parse(":/x:[a=:/y,\nb=:nosuch]").unwrap_err();
+        assert_eq!("invalid filter", e.message);
+        assert_eq!(((2, 3), (2, 10)), (e.start, e.end));
+        assert_eq!(":nosuch", e.found);
+
+        let e = try_parse(":/x:[a").unwrap_err();
+        assert_eq!(((1, 6), (1, 6)), (e.start, e.end));
+        assert_eq!(vec!["filter".to_string()], e.expected);
+    }
+
+    #[test]
+    fn pretty_workspace_test() {
+        assert_eq!(
+            "$common = :/lib\n\nlib = $common\n::x/",
+            pretty_workspace("$common = :/lib\nlib = $common\nx = :/x").unwrap()
+        );
+        assert_eq!(
+            "$other = :/x\n\n::lib/",
+            pretty_workspace("$other = :/x\nlib = :/lib").unwrap()
+        );
+    }
+
+    #[test]
+    fn quote_test() {
+        // All of them are valid regular expressions, some end in a backslash
+        for arg in &[
+            "a\\\\",
+            "\\\\\\\\",
+            "a\\\\\"b",
+            "\"quoted\"",
+            "x\\d+\\.y",
+            "a \\\\ b",
+        ] {
+            for op in &[
+                Op::Regex(arg.to_string()),
+                Op::StripMessage(arg.to_string()),
+                Op::Replace(arg.to_string(), arg.to_string(), arg.to_string()),
+                Op::Patterns(vec![arg.to_string(), "b".to_string()]),
+            ] {
+                let filter = to_filter(op.clone());
+                assert_eq!(filter, parse(&spec(filter)).unwrap(), "{}", spec(filter));
+            }
+        }
+
+        // Backslashes are only escaped when needed, so regular expressions stay readable
+        assert_eq!(
+            ":regex=\"a\\.b\\d\\\\\\\\\"",
+            spec(to_filter(Op::Regex("a\\.b\\d\\\\".to_string())))
+        );
+        assert_eq!(
+            to_filter(Op::Message("a\\.b\\\"".to_string())),
+            parse(":message=\"a\\.b\\\\\\\"\"").unwrap()
+        );
+
+        for path in &["a b", "a\"b", "x,y"] {
+            for op in &[
+                Op::Subdir(PathBuf::from(path)),
+                Op::Prefix(PathBuf::from(path)),
+                Op::File(PathBuf::from(path), PathBuf::from(path)),
+                Op::File(PathBuf::from("c"), PathBuf::from(path)),
+                Op::Workspace(PathBuf::from(path)),
+            ] {
+                let filter = to_filter(op.clone());
+                assert_eq!(filter, parse(&spec(filter)).unwrap(), "{}", spec(filter));
+            }
+        }
+        assert_eq!(
+            parse(":/a:prefix=a").unwrap(),
+            parse(&spec(parse("::\"a/\"").unwrap())).unwrap()
+        );
+    }
+
+    #[test]
+    fn filter_id_test() {
+        // Ids are used to name persistent caches, they must never change
+        assert_eq!(
+            "f76dd238ade08917e6712764a16a22005a50573d",
+            format!("{}", nop().id())
+        );
+        assert_eq!(
+            "2220301ba689777d5fc50d60b8eed1f9d0b7ac46",
+            format!("{}", parse(":/a").unwrap().id())
+        );
+        assert_eq!(
+            "6292211f4c97081413970728f7dddd10fdd74d4c",
+            format!("{}", parse(":/a:[::b/,:exclude[::c]]").unwrap().id())
+        );
+    }
+
+    #[test]
+    fn encoding_test() {
+        // The encoding determines the ids, it must never change
+        assert_eq!(vec![0x00], encoding::encode(&Op::Nop));
+        assert_eq!(vec![0x03], encoding::encode(&Op::Squash(None)));
+        assert_eq!(
+            vec![0x12, 3, 0, 0, 0, b'a', b'/', b'b'],
+            encoding::encode(&Op::Subdir(PathBuf::from("a/b")))
+        );
+        assert_eq!(
+            vec![0x10, 1, 0, 0, 0, b'c', 1, 0, 0, 0, b'd'],
+            encoding::encode(&Op::File(PathBuf::from("c"), PathBuf::from("d")))
+        );
+        assert_eq!(
+            vec![0x25, 10, 0, 0, 0, 0, 0, 0, 0, 1],
+            encoding::encode(&Op::MaxSize(10, true))
+        );
+        assert_eq!(
+            vec![0x19, 1, 0x80, 0x51, 0x01, 0, 0, 0, 0, 0],
+            encoding::encode(&Op::Squash(Some(SquashBoundary::Time(86400))))
+        );
+        assert_eq!(
+            vec![0x22, 2, 0, 0, 0, 1, 0, 0, 0, b'a', 1, 0, 0, 0, b'b'],
+            encoding::encode(&Op::Patterns(vec!["a".to_string(), "b".to_string()]))
+        );
+
+        let a = parse(":/a").unwrap();
+        let b = parse("::b").unwrap();
+        let mut chain = vec![0x41];
+        chain.extend_from_slice(a.id().as_bytes());
+        chain.extend_from_slice(b.id().as_bytes());
+        assert_eq!(chain, encoding::encode(&Op::Chain(a, b)));
+    }
+
+    #[test]
+    fn equal_filter_id_test() {
+        let s = ":/a:[::b/,c=:/d]:exclude[::e]";
+        assert_eq!(parse(s).unwrap().id(), parse(s).unwrap().id());
+        assert_eq!(
+            parse(s).unwrap().id(),
+            parse(&spec(parse(s).unwrap())).unwrap().id()
+        );
+        assert_eq!(
+            parse(":[::b/,c=:/d]").unwrap().id(),
+            to_filter(Op::Compose(vec![
+                to_filter(Op::Chain(
+                    to_filter(Op::Subdir(PathBuf::from("b"))),
+                    to_filter(Op::Prefix(PathBuf::from("b")))
+                )),
+                to_filter(Op::Chain(
+                    to_filter(Op::Subdir(PathBuf::from("d"))),
+                    to_filter(Op::Prefix(PathBuf::from("c")))
+                )),
+            ]))
+            .id()
+        );
+
+        assert_ne!(parse(":/a").unwrap().id(), parse(":prefix=a").unwrap().id());
+        assert_ne!(
+            parse(":[:/a,:/b]").unwrap().id(),
+            parse(":[:/b,:/a]").unwrap().id()
+        );
+        assert_ne!(
+            parse(":maxsize=10").unwrap().id(),
+            parse(":maxsize=10,stub").unwrap().id()
+        );
+    }
+
+    #[test]
+    fn footprint_test() {
+        let f = |spec: &str| footprint(parse(spec).unwrap());
+        assert_eq!(vec!["a/b", "a/d"], f(":/a:[::b/,c=:/d]"));
+        assert_eq!(vec!["x/y"], f(":/x::y"));
+        assert_eq!(vec!["b"], f(":prefix=a:/a/b"));
+        assert_eq!(vec!["a"], f(":/a:exclude[::e]"));
+        assert_eq!(vec!["**"], f(":workspace=ws"));
+        assert_eq!(Vec::<String>::new(), f(":empty"));
+
+        let patterns = f(":/a:[::b/,c=:/d]");
+        assert!(footprint_matches(&patterns, Path::new("a/b/file")));
+        assert!(!footprint_matches(&patterns, Path::new("a/c/file")));
+    }
 }
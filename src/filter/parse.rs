@@ -1,32 +1,191 @@
 use super::*;
 
 fn make_op(args: &[&str]) -> JoshResult<Op> {
-    match args {
-        ["nop"] => Ok(Op::Nop),
-        ["empty"] => Ok(Op::Empty),
-        ["prefix", arg] => Ok(Op::Prefix(Path::new(arg).to_owned())),
-        ["workspace", arg] => Ok(Op::Workspace(Path::new(arg).to_owned())),
-        ["SQUASH"] => Ok(Op::Squash),
-        ["PATHS"] => Ok(Op::Paths),
-        ["FOLD"] => Ok(Op::Fold),
-        _ => Err(josh_error("invalid filter")),
+    let op = match args {
+        ["nop"] => Op::Nop,
+        ["empty"] => Op::Empty,
+        ["prefix", arg] => Op::Prefix(Path::new(arg).to_owned()),
+        ["workspace", arg] => Op::Workspace(Path::new(arg).to_owned()),
+        ["include", arg] => Op::Include(Path::new(arg).to_owned()),
+        ["repo", arg] => match arg.splitn(2, '@').collect::<Vec<_>>().as_slice() {
+            [repo, refname] if *repo != "" && *refname != "" => {
+                Op::Repo(repo.to_string(), refname.to_string())
+            }
+            _ => {
+                return Err(josh_error(&format!(
+                    "expected :repo=<repo>@<ref>, got {:?}",
+                    arg
+                )))
+            }
+        },
+        ["authors", arg] => Op::Authors(Path::new(arg).to_owned()),
+        ["message", arg] => Op::Message(arg.to_string()),
+        ["replace", pattern, regex, replacement] => Op::Replace(
+            pattern.to_string(),
+            regex.to_string(),
+            replacement.to_string(),
+        ),
+        ["submodules"] => Op::Submodules,
+        ["attr", arg] => Op::Attr(arg.to_string()),
+        ["maxsize", size] => Op::MaxSize(parse_size(size)?, false),
+        ["maxsize", size, "stub"] => Op::MaxSize(parse_size(size)?, true),
+        ["regex", arg] => Op::Regex(arg.to_string()),
+        ["patterns", patterns @ ..] if patterns.len() > 0 => {
+            Op::Patterns(patterns.iter().map(|x| x.to_string()).collect())
+        }
+        ["patterns_file", arg] => Op::PatternsFile(Path::new(arg).to_owned()),
+        ["strip_message", arg] => Op::StripMessage(arg.to_string()),
+        ["SQUASH"] => Op::Squash(None),
+        ["SQUASH", arg] => Op::Squash(Some(parse_boundary(arg)?)),
+        ["PATHS"] => Op::Paths,
+        ["FOLD"] => Op::Fold,
+        ["linear"] => Op::Linear,
+        _ => return Err(josh_error("invalid filter")),
+    };
+    validate(&op)?;
+    Ok(op)
+}
+
+/// Check the arguments of `op`, so that invalid ones are found before applying it.
+/// Used for filters that are not parsed from a spec as well, see `Ast::to_filter`.
+pub(super) fn validate(op: &Op) -> JoshResult<()> {
+    match op {
+        Op::Repo(repo, refname) if repo == "" || refname == "" => {
+            return Err(josh_error(&format!(
+                "expected :repo=<repo>@<ref>, got {:?}",
+                format!("{}@{}", repo, refname)
+            )));
+        }
+        Op::Message(template) => validate_template(template)?,
+        Op::Replace(pattern, regex, _) => {
+            glob::Pattern::new(pattern)
+                .map_err(|_| josh_error(&format!("invalid pattern {:?}", pattern)))?;
+            regex::Regex::new(regex)
+                .map_err(|_| josh_error(&format!("invalid regex {:?}", regex)))?;
+        }
+        Op::Regex(regex) => {
+            path_regex(regex)?;
+        }
+        Op::Patterns(patterns) => {
+            if patterns.is_empty() {
+                return Err(josh_error("expected at least one pattern"));
+            }
+            patterns::PatternList::parse(patterns)?;
+        }
+        Op::StripMessage(regex) => {
+            regex::Regex::new(regex)
+                .map_err(|_| josh_error(&format!("invalid regex {:?}", regex)))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A boundary is either a full commit sha, a date (`2021-05-01`) or a time in RFC 3339
+/// format (`2021-05-01T12:00:00Z`). Dates and times without offset are in UTC.
+fn parse_boundary(arg: &str) -> JoshResult<SquashBoundary> {
+    if arg.len() == 40 && arg.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(SquashBoundary::Commit(arg.to_lowercase()));
     }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(arg, "%Y-%m-%d") {
+        return Ok(SquashBoundary::Time(date.and_hms(0, 0, 0).timestamp()));
+    }
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(arg) {
+        return Ok(SquashBoundary::Time(time.timestamp()));
+    }
+    Err(josh_error(&format!(
+        "expected a commit sha or a date in :SQUASH=, got {:?}",
+        arg
+    )))
 }
 
-fn parse_item(pair: pest::iterators::Pair<Rule>) -> JoshResult<Op> {
+/// Render the template of `:message=` once, so errors are found before applying it
+fn validate_template(arg: &str) -> JoshResult<()> {
+    let vars: std::collections::HashMap<String, &str> = ["message", "subject", "body"]
+        .iter()
+        .map(|x| (x.to_string(), ""))
+        .collect();
+    strfmt::strfmt(arg, &vars)
+        .map_err(|e| josh_error(&format!("invalid template {:?}: {}", arg, e)))?;
+    Ok(())
+}
+
+fn parse_size(arg: &str) -> JoshResult<usize> {
+    arg.parse()
+        .map_err(|_| josh_error(&format!("invalid size {:?}", arg)))
+}
+
+/// Named filters defined with `$name = ...`, references to them are resolved while parsing
+struct Definitions {
+    filters: Vec<(String, Filter)>,
+}
+
+impl Definitions {
+    fn get(&self, name: &str) -> JoshResult<Filter> {
+        let (_, filter) = self
+            .filters
+            .iter()
+            .find(|(n, _)| n == name)
+            .ok_or(josh_error(&format!("undefined filter ${}", name)))?;
+        Ok(*filter)
+    }
+
+    fn define(&mut self, name: &str, filter: Filter) -> JoshResult<()> {
+        if self.filters.iter().any(|(n, _)| n == name) {
+            return Err(josh_error(&format!("filter ${} defined twice", name)));
+        }
+        self.filters.push((name.to_string(), filter));
+        Ok(())
+    }
+}
+
+/// Remove the quotes around a string argument. Inside of it `\"` stands for `"` and
+/// `\\` for `\`, other backslashes are kept, so regular expressions like `\d` can be
+/// written as usual.
+fn unquote(arg: &str) -> String {
+    if !(arg.len() >= 2 && arg.starts_with('"') && arg.ends_with('"')) {
+        return arg.to_string();
+    }
+    let mut out = String::new();
+    let mut chars = arg[1..arg.len() - 1].chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('\\')) | ('\\', Some('"')) => out.push(chars.next().unwrap()),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn parse_item(pair: pest::iterators::Pair<Rule>, defs: &mut Definitions) -> LocatedResult<Op> {
+    let span = pair.as_span();
+    parse_item2(pair, defs).map_err(|e| e.or_at(&span))
+}
+
+fn parse_item2(pair: pest::iterators::Pair<Rule>, defs: &mut Definitions) -> LocatedResult<Op> {
     match pair.as_rule() {
         Rule::filter => {
-            let v: Vec<_> = pair.into_inner().map(|x| x.as_str()).collect();
-            make_op(v.as_slice())
+            let v: Vec<_> = pair.into_inner().map(|x| unquote(x.as_str())).collect();
+            let v: Vec<&str> = v.iter().map(|x| x.as_str()).collect();
+            Ok(make_op(v.as_slice())?)
         }
         Rule::filter_nop => Ok(Op::Nop),
         Rule::filter_subdir => Ok(Op::Subdir(
-            Path::new(pair.into_inner().next().unwrap().as_str()).to_owned(),
+            Path::new(&unquote(pair.into_inner().next().unwrap().as_str())).to_owned(),
         )),
         Rule::filter_presub => {
             let mut inner = pair.into_inner();
-            let arg = inner.next().unwrap().as_str();
-            if arg.ends_with("/") {
+            let arg = unquote(inner.next().unwrap().as_str());
+            let arg = arg.as_str();
+            if let Some(src) = inner.next().map(|x| unquote(x.as_str())) {
+                if src.ends_with("/") || src.contains("*") || arg.ends_with("/") {
+                    return Err(josh_error("only single files can be renamed").into());
+                }
+                Ok(Op::File(
+                    Path::new(arg).to_owned(),
+                    Path::new(&src).to_owned(),
+                ))
+            } else if arg.ends_with("/") {
                 let arg = arg.trim_end_matches("/");
                 Ok(Op::Chain(
                     to_filter(Op::Subdir(std::path::PathBuf::from(arg))),
@@ -35,138 +194,180 @@ fn parse_item(pair: pest::iterators::Pair<Rule>) -> JoshResult<Op> {
             } else if arg.contains("*") {
                 Ok(Op::Glob(arg.to_string()))
             } else {
-                Ok(Op::File(Path::new(arg).to_owned()))
+                Ok(Op::File(
+                    Path::new(arg).to_owned(),
+                    Path::new(arg).to_owned(),
+                ))
             }
         }
         Rule::filter_noarg => {
             let mut inner = pair.into_inner();
-            make_op(&[inner.next().unwrap().as_str()])
+            Ok(make_op(&[inner.next().unwrap().as_str()])?)
         }
+        Rule::filter_ref => Ok(to_op(defs.get(pair.into_inner().next().unwrap().as_str())?)),
         Rule::filter_group => {
-            let v: Vec<_> = pair.into_inner().map(|x| x.as_str()).collect();
+            let v: Vec<_> = pair.into_inner().collect();
 
             match v.as_slice() {
-                [args] => Ok(Op::Compose(parse_group(args)?)),
+                [args] => Ok(Op::Compose(parse_group(args, defs)?)),
                 [cmd, args] => {
-                    let g = parse_group(args)?;
-                    match *cmd {
+                    let g = parse_group(args, defs)?;
+                    match cmd.as_str() {
                         "exclude" => {
                             Ok(Op::Subtract(to_filter(Op::Nop), to_filter(Op::Compose(g))))
                         }
                         "subtract" if g.len() == 2 => Ok(Op::Subtract(g[0], g[1])),
-                        _ => Err(josh_error("parse_item: no match")),
+                        "subtract" => Err(Located::at(
+                            &args.as_span(),
+                            "expected exactly two filters in :subtract",
+                        )),
+                        _ => Err(Located::at(&cmd.as_span(), "invalid filter")),
                     }
                 }
-                _ => Err(josh_error("parse_item: no match {:?}")),
+                _ => Err(josh_error("parse_item: no match").into()),
             }
         }
-        _ => Err(josh_error("parse_item: no match")),
+        _ => Err(josh_error("parse_item: no match").into()),
     }
 }
 
 fn parse_file_entry(
     pair: pest::iterators::Pair<Rule>,
     filters: &mut Vec<Filter>,
-) -> JoshResult<()> {
+    defs: &mut Definitions,
+) -> LocatedResult<()> {
     match pair.as_rule() {
         Rule::file_entry => {
             let mut inner = pair.into_inner();
-            let path = inner.next().unwrap().as_str();
-            let filter = inner
-                .next()
-                .map(|x| x.as_str().to_owned())
-                .unwrap_or(format!(":/{}", path));
-            let filter = parse(&filter)?;
-            let filter = chain(filter, to_filter(Op::Prefix(Path::new(path).to_owned())));
+            let path = inner.next().unwrap();
+            let filter = if let Some(filter) = inner.next() {
+                parse_pair(&filter, defs)?
+            } else {
+                parse2(&format!(":/{}", path.as_str()), defs)
+                    .map_err(|e| e.shift(path.as_span().start()))?
+            };
+            let filter = chain(
+                filter,
+                to_filter(Op::Prefix(Path::new(path.as_str()).to_owned())),
+            );
             filters.push(filter);
             Ok(())
         }
         Rule::filter_spec => {
-            let filter = pair.as_str();
-            filters.push(parse(&filter)?);
+            filters.push(parse_pair(&pair, defs)?);
             Ok(())
         }
+        Rule::definition => {
+            let mut inner = pair.into_inner();
+            let name = inner.next().unwrap();
+            let span = name.as_span();
+            let filter = parse_pair(&inner.next().unwrap(), defs)?;
+            defs.define(name.into_inner().next().unwrap().as_str(), filter)
+                .map_err(|e| Located::at(&span, &e.0))
+        }
         Rule::EOI => Ok(()),
-        _ => Err(josh_error(&format!("invalid workspace file {:?}", pair))),
+        _ => Err(josh_error(&format!("invalid workspace file {:?}", pair)).into()),
     }
 }
 
-fn parse_group(filter_spec: &str) -> JoshResult<Vec<Filter>> {
+/// Parse the text of `pair` as a filter, errors are located relative to the input
+/// `pair` belongs to.
+fn parse_pair(pair: &pest::iterators::Pair<Rule>, defs: &mut Definitions) -> LocatedResult<Filter> {
+    parse2(pair.as_str(), defs).map_err(|e| e.shift(pair.as_span().start()))
+}
+
+fn parse_group(
+    pair: &pest::iterators::Pair<Rule>,
+    defs: &mut Definitions,
+) -> LocatedResult<Vec<Filter>> {
     rs_tracing::trace_scoped!("parse_group");
     let mut filters = vec![];
 
-    match Grammar::parse(Rule::compose, filter_spec) {
-        Ok(mut r) => {
-            let r = r.next().unwrap();
-            for pair in r.into_inner() {
-                parse_file_entry(pair, &mut filters)?;
-            }
-
-            return Ok(filters);
-        }
-        Err(r) => {
-            return Err(josh_error(&format!(
-                "Invalid workspace:\n----\n{}\n\n{}\n----",
-                r.to_string().replace("␊", ""),
-                filter_spec
-            )));
-        }
+    let offset = pair.as_span().start();
+    let mut r = Grammar::parse(Rule::compose, pair.as_str())
+        .map_err(|e| Located::from_pest(pair.as_str(), e).shift(offset))?;
+    let r = r.next().unwrap();
+    for pair in r.into_inner() {
+        parse_file_entry(pair, &mut filters, defs).map_err(|e| e.shift(offset))?;
     }
+
+    return Ok(filters);
 }
 
-fn parse_workspace(filter_spec: &str) -> JoshResult<Vec<Filter>> {
+fn parse_workspace(filter_spec: &str, defs: &mut Definitions) -> LocatedResult<Vec<Filter>> {
     rs_tracing::trace_scoped!("parse_workspace");
 
-    match Grammar::parse(Rule::workspace_file, filter_spec) {
-        Ok(mut r) => {
-            let r = r.next().unwrap();
-            for pair in r.into_inner() {
-                match pair.as_rule() {
-                    Rule::compose => {
-                        let filters = parse_group(pair.as_str())?;
-                        return Ok(filters);
-                    }
-                    Rule::workspace_comments => {
-                        continue;
-                    }
-                    _ => return Err(josh_error(&format!("invalid workspace file {:?}", pair))),
-                };
+    let mut r = Grammar::parse(Rule::workspace_file, filter_spec)
+        .map_err(|e| Located::from_pest(filter_spec, e))?;
+    let r = r.next().unwrap();
+    for pair in r.into_inner() {
+        match pair.as_rule() {
+            Rule::compose => {
+                return parse_group(&pair, defs);
             }
-            return Err(josh_error(&format!("invalid workspace file")));
-        }
-        Err(r) => {
-            return Err(josh_error(&format!(
-                "Invalid workspace:\n----\n{}\n\n{}\n----",
-                r.to_string().replace("␊", ""),
-                filter_spec
-            )));
-        }
+            Rule::workspace_comments => {
+                continue;
+            }
+            _ => return Err(josh_error(&format!("invalid workspace file {:?}", pair)).into()),
+        };
     }
+    return Err(josh_error("invalid workspace file").into());
 }
 
 /// Create a `Filter` from a string representation
 pub fn parse(filter_spec: &str) -> JoshResult<Filter> {
+    Ok(try_parse(filter_spec)?)
+}
+
+/// Like `parse`, but returns a `ParseError` describing the location of the problem
+pub fn try_parse(filter_spec: &str) -> Result<Filter, ParseError> {
+    parse2(filter_spec, &mut Definitions { filters: vec![] })
+        .map_err(|e| ParseError::new(filter_spec, e))
+}
+
+/// Parse a workspace file like `parse`, but also return the named filters it defines
+/// in order, so they can be kept when printing it.
+pub fn parse_with_definitions(filter_spec: &str) -> JoshResult<(Vec<(String, Filter)>, Filter)> {
+    let mut defs = Definitions { filters: vec![] };
+    let filter = parse2(filter_spec, &mut defs).map_err(|e| ParseError::new(filter_spec, e))?;
+    Ok((defs.filters, filter))
+}
+
+fn parse2(filter_spec: &str, defs: &mut Definitions) -> LocatedResult<Filter> {
     if filter_spec == "" {
         return Ok(to_filter(Op::Empty));
     }
-    let mut chain: Option<Op> = None;
-    if let Ok(r) = Grammar::parse(Rule::filter_chain, filter_spec) {
-        let mut r = r;
-        let r = r.next().unwrap();
-        for pair in r.into_inner() {
-            let v = parse_item(pair)?;
-            chain = Some(if let Some(c) = chain {
-                Op::Chain(to_filter(c), to_filter(v))
-            } else {
-                v
-            });
+    let chain_error = match Grammar::parse(Rule::filter_chain, filter_spec) {
+        Ok(mut r) => {
+            let mut chain: Option<Op> = None;
+            let r = r.next().unwrap();
+            for pair in r.into_inner() {
+                let v = parse_item(pair, defs).map_err(|e| Located {
+                    workspace: false,
+                    ..e
+                })?;
+                chain = Some(if let Some(c) = chain {
+                    Op::Chain(to_filter(c), to_filter(v))
+                } else {
+                    v
+                });
+            }
+            return Ok(opt::optimize(to_filter(chain.unwrap_or(Op::Nop))));
         }
-        return Ok(opt::optimize(to_filter(chain.unwrap_or(Op::Nop))));
+        Err(e) => Located::from_pest(filter_spec, e),
     };
 
-    return Ok(opt::optimize(to_filter(Op::Compose(parse_workspace(
-        filter_spec,
-    )?))));
+    // Report the error of whichever interpretation got further
+    match parse_workspace(filter_spec, defs) {
+        Ok(filters) => Ok(opt::optimize(to_filter(Op::Compose(filters)))),
+        Err(e) if e.range.start < chain_error.range.start => Err(chain_error),
+        // Single line filters are not meant to be workspace files
+        Err(e) if e.range.start == chain_error.range.start && !filter_spec.contains('\n') => Err(e),
+        Err(e) => Err(Located {
+            workspace: true,
+            ..e
+        }),
+    }
 }
 
 /// Get the potential leading comments from a workspace.josh as a string
@@ -192,6 +393,222 @@ pub fn get_comments(filter_spec: &str) -> JoshResult<String> {
     )));
 }
 
+/// A problem found in a filter spec. `start` and `end` are pairs of line and
+/// column (both starting at 1) enclosing the offending input. For syntax errors
+/// `expected` lists what would have been accepted at `start`. `workspace` is set
+/// if the input was read as a workspace file.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ParseError {
+    pub message: String,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub expected: Vec<String>,
+    pub found: String,
+    pub input: String,
+    pub workspace: bool,
+}
+
+impl ParseError {
+    fn new(input: &str, located: Located) -> ParseError {
+        let found = if located.range.start == located.range.end {
+            let rest = &input[located.range.start..];
+            rest.lines().next().unwrap_or("").to_string()
+        } else {
+            input[located.range.clone()].to_string()
+        };
+        let message = located.message.unwrap_or_else(|| {
+            if found == "" {
+                "unexpected end of input".to_string()
+            } else {
+                format!("unexpected {:?}", found)
+            }
+        });
+        ParseError {
+            message,
+            start: line_col(input, located.range.start),
+            end: line_col(input, located.range.end),
+            expected: located.expected,
+            found,
+            input: input.to_string(),
+            workspace: located.workspace,
+        }
+    }
+}
+
+fn line_col(input: &str, pos: usize) -> (usize, usize) {
+    let before = &input[..pos];
+    let line_start = before.rfind('\n').map(|x| x + 1).unwrap_or(0);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Renders the error in the same way pest does, with the offending input
+/// underlined:
+///
+/// ```text
+/// unexpected "[b"
+///  --> 1:4
+///   |
+/// 1 | :/a[b
+///   |    ^---
+///   |
+///   = expected end of input or filter
+/// ```
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.workspace {
+            writeln!(f, "Invalid workspace:\n----")?;
+        }
+        let (line, col) = self.start;
+        let number = line.to_string();
+        let pad = " ".repeat(number.len());
+        let text = self.input.lines().nth(line - 1).unwrap_or("");
+
+        let len = if self.end.0 == line {
+            self.end.1 - col
+        } else {
+            text.chars().count() + 1 - col
+        };
+        let marker = match len {
+            0 => "^---".to_string(),
+            1 => "^".to_string(),
+            n => format!("^{}^", "-".repeat(n - 2)),
+        };
+
+        writeln!(f, "{}", self.message)?;
+        writeln!(f, "{}--> {}:{}", pad, line, col)?;
+        writeln!(f, "{} |", pad)?;
+        writeln!(f, "{} | {}", number, text)?;
+        write!(f, "{} | {}{}", pad, " ".repeat(col - 1), marker)?;
+        if !self.expected.is_empty() {
+            write!(f, "\n{} |\n{} = expected ", pad, pad)?;
+            for (i, e) in self.expected.iter().enumerate() {
+                if i > 0 {
+                    write!(
+                        f,
+                        "{}",
+                        if i + 1 == self.expected.len() {
+                            " or "
+                        } else {
+                            ", "
+                        }
+                    )?;
+                }
+                write!(f, "{}", e)?;
+            }
+        }
+        if self.workspace {
+            write!(f, "\n\n{}\n----", self.input)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::convert::From<ParseError> for JoshError {
+    fn from(e: ParseError) -> JoshError {
+        josh_error(&e.to_string())
+    }
+}
+
+/// A `ParseError` with the position given as a byte range relative to the string
+/// being parsed. Nested parsers shift it to the position in the outer string.
+struct Located {
+    range: std::ops::Range<usize>,
+    message: Option<String>,
+    expected: Vec<String>,
+    placed: bool,
+    workspace: bool,
+}
+
+type LocatedResult<T> = Result<T, Located>;
+
+impl Located {
+    fn at(span: &pest::Span, message: &str) -> Located {
+        Located {
+            range: span.start()..span.end(),
+            message: Some(message.to_string()),
+            expected: vec![],
+            placed: true,
+            workspace: false,
+        }
+    }
+
+    fn from_pest(input: &str, e: pest::error::Error<Rule>) -> Located {
+        let range = match e.location {
+            pest::error::InputLocation::Pos(p) => p..p,
+            pest::error::InputLocation::Span((s, e)) => s..e,
+        };
+        let mut expected: Vec<String> = vec![];
+        if let pest::error::ErrorVariant::ParsingError { positives, .. } = &e.variant {
+            for rule in positives {
+                let name = rule_name(*rule).to_string();
+                if !expected.contains(&name) {
+                    expected.push(name);
+                }
+            }
+        }
+        let range = if range.end > input.len() {
+            input.len()..input.len()
+        } else {
+            range
+        };
+        Located {
+            range,
+            message: None,
+            expected,
+            placed: true,
+            workspace: false,
+        }
+    }
+
+    /// Use `span` as location if the error did not get one yet
+    fn or_at(self, span: &pest::Span) -> Located {
+        if self.placed {
+            return self;
+        }
+        Located {
+            range: span.start()..span.end(),
+            placed: true,
+            ..self
+        }
+    }
+
+    fn shift(self, offset: usize) -> Located {
+        Located {
+            range: self.range.start + offset..self.range.end + offset,
+            ..self
+        }
+    }
+}
+
+impl std::convert::From<JoshError> for Located {
+    fn from(e: JoshError) -> Located {
+        Located {
+            range: 0..0,
+            message: Some(e.0),
+            expected: vec![],
+            placed: false,
+            workspace: false,
+        }
+    }
+}
+
+fn rule_name(rule: Rule) -> &'static str {
+    match rule {
+        Rule::EOI => "end of input",
+        Rule::dst_path | Rule::path => "path",
+        Rule::argument | Rule::string => "argument",
+        Rule::cmd => "filter name",
+        Rule::name => "name",
+        Rule::definition => "definition",
+        Rule::workspace_comments => "comment",
+        Rule::workspace_file | Rule::compose => "workspace entry",
+        _ => "filter",
+    }
+}
+
 #[derive(Parser)]
 #[grammar = "filter/grammar.pest"]
 struct Grammar;
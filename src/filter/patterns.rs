@@ -0,0 +1,138 @@
+/*
+ * Matching of paths against pattern lists in gitignore syntax
+ * (see https://git-scm.com/docs/gitignore#_pattern_format).
+ */
+
+use super::*;
+
+struct Pattern {
+    regex: regex::Regex,
+    negated: bool,
+    dir_only: bool,
+}
+
+pub struct PatternList(Vec<Pattern>);
+
+pub fn to_regex(pattern: &str) -> String {
+    let anchored = pattern.contains('/');
+    let pattern = pattern.trim_start_matches('/');
+    let mut result = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    result.push_str("(?:.*/)?");
+                } else {
+                    result.push_str(".*");
+                }
+            }
+            '*' => result.push_str("[^/]*"),
+            '?' => result.push_str("[^/]"),
+            '[' => {
+                let mut class = String::from("[");
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    class.push('^');
+                }
+                let mut closed = false;
+                while let Some(c) = chars.next() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        class.push('\\');
+                    }
+                    class.push(c);
+                }
+                if closed {
+                    result.push_str(&class);
+                    result.push(']');
+                } else {
+                    result.push_str(&regex::escape(&class));
+                }
+            }
+            '\\' => {
+                if let Some(e) = chars.next() {
+                    result.push_str(&regex::escape(&e.to_string()));
+                }
+            }
+            c => result.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    result.push('$');
+    return result;
+}
+
+impl PatternList {
+    pub fn parse<S: AsRef<str>>(lines: &[S]) -> JoshResult<PatternList> {
+        let mut patterns = vec![];
+        for line in lines {
+            let line = line.as_ref();
+            if line.trim() == "" || line.starts_with('#') {
+                continue;
+            }
+            let mut line = if line.ends_with("\\ ") {
+                line
+            } else {
+                line.trim_end()
+            };
+            let negated = line.starts_with('!');
+            if negated {
+                line = &line[1..];
+            }
+            let dir_only = line.ends_with('/');
+            let line = line.trim_end_matches('/');
+            patterns.push(Pattern {
+                regex: regex::Regex::new(&to_regex(line))
+                    .map_err(|_| josh_error(&format!("invalid pattern {:?}", line)))?,
+                negated,
+                dir_only,
+            });
+        }
+        Ok(PatternList(patterns))
+    }
+
+    /// Parse the content of a pattern file, one pattern per line
+    pub fn from_file(content: &str) -> JoshResult<PatternList> {
+        PatternList::parse(&content.lines().collect::<Vec<_>>())
+    }
+
+    /// Check if the list matches `path`. The last pattern that matches either the path
+    /// itself or one of its parent directories decides. Unlike in gitignore, this means
+    /// that files inside of a matched directory can be excluded again by later patterns.
+    pub fn matches(&self, path: &std::path::Path, is_dir: bool) -> bool {
+        if is_dir && self.0.iter().any(|p| p.negated) {
+            // Some content of the directory might not be matched
+            return false;
+        }
+
+        let path = path.to_string_lossy();
+        let mut dirs = vec![];
+        let mut prefix = String::new();
+        for component in path.split('/') {
+            if prefix != "" {
+                dirs.push(prefix.clone());
+                prefix.push('/');
+            }
+            prefix.push_str(component);
+        }
+        if is_dir {
+            dirs.push(prefix.clone());
+        }
+
+        self.0
+            .iter()
+            .rev()
+            .find(|p| {
+                (!(is_dir || p.dir_only) && p.regex.is_match(&path))
+                    || dirs.iter().any(|d| p.regex.is_match(d))
+            })
+            .map(|p| !p.negated)
+            .unwrap_or(false)
+    }
+}
@@ -62,7 +62,18 @@ pub fn remove_pred<'a>(
     key: git2::Oid,
 ) -> super::JoshResult<git2::Tree<'a>> {
     let repo = transaction.repo();
-    if let Some(cached) = transaction.get_glob((input, key)) {
+    // The predicate depends on the path, so the same tree can have different results
+    // depending on where it is located.
+    let cache_key = if root == "" {
+        (input, key)
+    } else {
+        let s = format!("{:?}", (key, root));
+        (
+            input,
+            git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes()).expect("hash_object"),
+        )
+    };
+    if let Some(cached) = transaction.get_glob(cache_key) {
         return Ok(repo.find_tree(cached)?);
     }
     rs_tracing::trace_scoped!("remove_pred X", "root": root);
@@ -115,7 +126,171 @@ pub fn remove_pred<'a>(
         }
     }
 
-    transaction.insert_glob((input, key), result.id());
+    transaction.insert_glob(cache_key, result.id());
+    return Ok(result);
+}
+
+/// Remove all files of the tree `input` for which the attribute `attr` is set in
+/// `.gitattributes` files of the tree or in `inherited` ones from parent directories.
+/// Directories are only removed when they become empty.
+pub fn remove_attr<'a>(
+    transaction: &'a cache::Transaction,
+    attr: &str,
+    root: &str,
+    input: git2::Oid,
+    inherited: &attributes::Attributes,
+    key: git2::Oid,
+) -> super::JoshResult<git2::Tree<'a>> {
+    let repo = transaction.repo();
+    let tree = repo.find_tree(input)?;
+
+    let attributes = if let Some(entry) = tree.get_name(".gitattributes") {
+        let content = tree::get_blob(&repo, &tree, std::path::Path::new(".gitattributes"));
+        inherited.with_file(root, entry.id(), &content, attr)
+    } else {
+        inherited.clone()
+    };
+
+    // The result depends on the location of the tree and on the rules of the parent
+    // directories
+    let s = format!("{:?}", (key, root, attributes.sources()));
+    let cache_key = (
+        input,
+        git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes())?,
+    );
+    if let Some(cached) = transaction.get_glob(cache_key) {
+        return Ok(repo.find_tree(cached)?);
+    }
+
+    let mut result = tree.clone();
+
+    for entry in tree.iter() {
+        let name = entry.name().ok_or(super::josh_error("INVALID_FILENAME"))?;
+        let path = format!("{}{}{}", root, if root == "" { "" } else { "/" }, name);
+
+        let id = if entry.kind() == Some(git2::ObjectType::Tree) {
+            remove_attr(transaction, attr, &path, entry.id(), &attributes, key)?.id()
+        } else if attributes.is_set(&path) {
+            git2::Oid::zero()
+        } else {
+            continue;
+        };
+
+        if id != entry.id() {
+            result = replace_child(
+                &repo,
+                &std::path::Path::new(name),
+                id,
+                entry.filemode(),
+                &result,
+            )?;
+        }
+    }
+
+    transaction.insert_glob(cache_key, result.id());
+    return Ok(result);
+}
+
+/// Replace all gitlinks in the tree `input` by the trees of the commits they point to.
+/// Gitlinks to commits that are not present in the repository are kept and counted
+/// with `Transaction::insert_missing_submodule`. Results containing those are not cached.
+pub fn inline_submodules<'a>(
+    transaction: &'a cache::Transaction,
+    input: git2::Oid,
+    key: git2::Oid,
+) -> super::JoshResult<git2::Tree<'a>> {
+    let repo = transaction.repo();
+    if let Some(cached) = transaction.get_glob((input, key)) {
+        return Ok(repo.find_tree(cached)?);
+    }
+
+    let missing_submodules = transaction.missing_submodules();
+    let tree = repo.find_tree(input)?;
+    let mut result = tree.clone();
+
+    for entry in tree.iter() {
+        let name = entry.name().ok_or(super::josh_error("INVALID_FILENAME"))?;
+        let subtree = match entry.kind() {
+            Some(git2::ObjectType::Tree) => entry.id(),
+            Some(git2::ObjectType::Commit) => ok_or!(repo.find_commit(entry.id()), {
+                transaction.insert_missing_submodule();
+                continue;
+            })
+            .tree_id(),
+            _ => continue,
+        };
+        let id = inline_submodules(transaction, subtree, key)?.id();
+
+        if id != entry.id() {
+            result = replace_child(&repo, &std::path::Path::new(name), id, 0o0040000, &result)?;
+        }
+    }
+
+    if transaction.missing_submodules() == missing_submodules {
+        transaction.insert_glob((input, key), result.id());
+    }
+    return Ok(result);
+}
+
+/// Replace every blob in the tree `input` by the result of `f`, called with the path
+/// and id of the blob. If `key` is given, results are cached, so `f` must only
+/// depend on its arguments and `key`.
+pub fn map_blobs<'a>(
+    transaction: &'a cache::Transaction,
+    root: &str,
+    input: git2::Oid,
+    f: &dyn Fn(&std::path::Path, git2::Oid) -> JoshResult<git2::Oid>,
+    key: Option<git2::Oid>,
+) -> super::JoshResult<git2::Tree<'a>> {
+    let repo = transaction.repo();
+    let cache_key = key.map(|key| {
+        let s = format!("{:?}", (key, root));
+        (
+            input,
+            git2::Oid::hash_object(git2::ObjectType::Blob, s.as_bytes()).expect("hash_object"),
+        )
+    });
+    if let Some(cached) = cache_key.and_then(|k| transaction.get_blobs(k)) {
+        return Ok(repo.find_tree(cached)?);
+    }
+    rs_tracing::trace_scoped!("map_blobs", "root": root);
+
+    let tree = repo.find_tree(input)?;
+    let mut result = tree.clone();
+
+    for entry in tree.iter() {
+        let name = entry.name().ok_or(super::josh_error("INVALID_FILENAME"))?;
+        let path = std::path::PathBuf::from(root).join(name);
+
+        let id = if entry.kind() == Some(git2::ObjectType::Blob) {
+            f(&path, entry.id())?
+        } else if entry.kind() == Some(git2::ObjectType::Tree) {
+            map_blobs(
+                transaction,
+                &format!("{}{}{}", root, if root == "" { "" } else { "/" }, name),
+                entry.id(),
+                f,
+                key,
+            )?
+            .id()
+        } else {
+            entry.id()
+        };
+
+        if id != entry.id() {
+            result = replace_child(
+                &repo,
+                &std::path::Path::new(name),
+                id,
+                entry.filemode(),
+                &result,
+            )?;
+        }
+    }
+
+    if let Some(k) = cache_key {
+        transaction.insert_blobs(k, result.id());
+    }
     return Ok(result);
 }
 
@@ -316,6 +491,25 @@ pub fn original_path(
     return pathline(&b);
 }
 
+/// Inverse of `original_path`: Find the path that the file `path` of `tree` has in
+/// the output of `filter`. Returns `None` if the file is not part of the output.
+/// Files that are mapped to several places only report one of them.
+pub fn mapped_path(
+    transaction: &cache::Transaction,
+    filter: Filter,
+    tree: git2::Tree,
+    path: &std::path::Path,
+) -> JoshResult<Option<std::path::PathBuf>> {
+    let paths_tree = apply(transaction, chain(to_filter(Op::Paths), filter), tree)?;
+    let ipaths = invert_paths(transaction, "", paths_tree)?;
+    let entry = ok_or!(ipaths.get_path(path), { return Ok(None) });
+    if entry.kind() != Some(git2::ObjectType::Blob) {
+        return Ok(None);
+    }
+    let b = get_blob(transaction.repo(), &ipaths, path);
+    return Ok(Some(std::path::PathBuf::from(b)));
+}
+
 pub fn repopulated_tree(
     transaction: &cache::Transaction,
     filter: Filter,
@@ -378,6 +572,11 @@ pub fn compose<'a>(
     let mut result = tree::empty(&repo);
     let mut taken = tree::empty(&repo);
     for (f, applied) in trees {
+        if filter::repo_name(*f).is_some() {
+            // Content of other repositories does not depend on the input tree
+            result = repo.find_tree(overlay(&repo, result.id(), applied.id())?)?;
+            continue;
+        }
         let tid = taken.id();
         let taken_applied = if let Some(cached) = transaction.get_apply(*f, tid) {
             cached
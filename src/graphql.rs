@@ -254,6 +254,35 @@ impl Warning {
     }
 }
 
+pub struct FilterError(filter::ParseError);
+
+#[graphql_object(context = Context)]
+impl FilterError {
+    fn message(&self) -> &str {
+        &self.0.message
+    }
+
+    fn line(&self) -> i32 {
+        self.0.start.0 as i32
+    }
+
+    fn column(&self) -> i32 {
+        self.0.start.1 as i32
+    }
+
+    fn end_line(&self) -> i32 {
+        self.0.end.0 as i32
+    }
+
+    fn end_column(&self) -> i32 {
+        self.0.end.1 as i32
+    }
+
+    fn expected(&self) -> &Vec<String> {
+        &self.0.expected
+    }
+}
+
 pub struct Path {
     path: std::path::PathBuf,
     commit_id: git2::Oid,
@@ -454,6 +483,15 @@ impl Path {
         })
     }
 
+    fn filter_error(&self, context: &Context) -> FieldResult<Option<FilterError>> {
+        self.internal_serialize(context, |transaction, id| {
+            let blob = transaction.repo().find_blob(id)?;
+            Ok(filter::try_parse(std::str::from_utf8(blob.content())?)
+                .err()
+                .map(FilterError))
+        })
+    }
+
     fn toml(&self, context: &Context) -> FieldResult<Document> {
         self.serialize_to_serde_value(context, |blob| {
             toml::de::from_str::<serde_json::Value>(blob)
@@ -17,6 +17,7 @@ pub fn walk2(
 
     let (known, n_new) = find_known(filter, input, transaction)?;
 
+    let squashed = |id| filter::is_squashed_by(transaction, filter, id).unwrap_or(false);
     let walk = {
         let mut walk = transaction.repo().revwalk()?;
         walk.set_sorting(git2::Sort::REVERSE | git2::Sort::TOPOLOGICAL)?;
@@ -24,7 +25,7 @@ pub fn walk2(
         for k in known.iter() {
             walk.hide(*k)?;
         }
-        walk
+        walk.with_hide_callback(&squashed)?
     };
 
     log::info!(
@@ -153,35 +154,303 @@ fn find_known(
             if k {
                 known.push(id)
             }
-            k
+            k || filter::is_squashed_by(transaction, filter, id).unwrap_or(false)
         })?
         .count();
     log::debug!("/find_known {}", n_new);
     return Ok((known, n_new));
 }
 
+/// Author, committer and message of a commit.
+/// Filters that rewrite commit metadata produce one of those to be used instead of
+/// the values found in the original commit.
+#[derive(Clone)]
+pub struct CommitMeta {
+    pub author: git2::Signature<'static>,
+    pub committer: git2::Signature<'static>,
+    pub message: String,
+}
+
+impl CommitMeta {
+    pub fn from_commit(commit: &git2::Commit) -> CommitMeta {
+        CommitMeta {
+            author: commit.author().to_owned(),
+            committer: commit.committer().to_owned(),
+            message: commit.message_raw().unwrap_or("no message").to_string(),
+        }
+    }
+
+    fn matches(&self, commit: &git2::Commit) -> bool {
+        let same = |a: &git2::Signature, b: &git2::Signature| {
+            a.name_bytes() == b.name_bytes()
+                && a.email_bytes() == b.email_bytes()
+                && a.when() == b.when()
+        };
+        same(&self.author, &commit.author())
+            && same(&self.committer, &commit.committer())
+            && Some(self.message.as_str()) == commit.message_raw()
+    }
+}
+
 // takes everything from base except it's tree and replaces it with the tree
-// given
+// given. If `meta` is given, it replaces author, committer and message as well.
 pub fn rewrite_commit(
     repo: &git2::Repository,
     base: &git2::Commit,
     parents: &[&git2::Commit],
     tree: &git2::Tree,
+    meta: Option<&CommitMeta>,
 ) -> JoshResult<git2::Oid> {
-    if base.tree()?.id() == tree.id() && all_equal(base.parents(), parents) {
+    if base.tree()?.id() == tree.id()
+        && all_equal(base.parents(), parents)
+        && meta.map(|m| m.matches(base)).unwrap_or(true)
+    {
         // Looks like an optimization, but in fact serves to not change the commit in case
         // it was signed.
         return Ok(base.id());
     }
 
+    let meta = meta
+        .cloned()
+        .unwrap_or_else(|| CommitMeta::from_commit(base));
+
+    let b =
+        repo.commit_create_buffer(&meta.author, &meta.committer, &meta.message, tree, parents)?;
+
+    return Ok(repo.odb()?.write(git2::ObjectType::Commit, &b)?);
+}
+
+/// Create a copy of the annotated tag `tag` pointing to the commit `target`, keeping its
+/// name, tagger and message. Signatures of the original tag don't match the copy and are
+/// removed, if `JOSH_TAG_SIGNING_KEY` is set the copy is signed with that gpg key instead.
+pub fn rewrite_tag(
+    repo: &git2::Repository,
+    tag: &git2::Tag,
+    target: git2::Oid,
+) -> JoshResult<git2::Oid> {
+    if tag.target_id() == target {
+        return Ok(tag.id());
+    }
+
+    let mut b = format!(
+        "object {}\ntype commit\ntag {}\n",
+        target,
+        String::from_utf8_lossy(tag.name_bytes())
+    )
+    .into_bytes();
+    if let Some(tagger) = tag.tagger() {
+        let when = tagger.when();
+        let offset = when.offset_minutes().abs();
+        b.extend_from_slice(b"tagger ");
+        b.extend_from_slice(tagger.name_bytes());
+        b.extend_from_slice(b" <");
+        b.extend_from_slice(tagger.email_bytes());
+        b.extend_from_slice(
+            format!(
+                "> {} {}{:02}{:02}\n",
+                when.seconds(),
+                when.sign(),
+                offset / 60,
+                offset % 60
+            )
+            .as_bytes(),
+        );
+    }
+    b.push(b'\n');
+
+    let message = tag.message_bytes().unwrap_or(b"");
+    let unsigned = [
+        "-----BEGIN PGP SIGNATURE-----",
+        "-----BEGIN SSH SIGNATURE-----",
+    ]
+    .iter()
+    .filter_map(|start| {
+        let start = start.as_bytes();
+        (0..message.len())
+            .filter(|&i| i == 0 || message[i - 1] == b'\n')
+            .find(|&i| message[i..].starts_with(start))
+    })
+    .min()
+    .unwrap_or(message.len());
+    b.extend_from_slice(&message[..unsigned]);
+
+    if let Ok(key) = std::env::var("JOSH_TAG_SIGNING_KEY") {
+        b.extend(gpg_sign(&b, &key)?);
+    }
+
+    return Ok(repo.odb()?.write(git2::ObjectType::Tag, &b)?);
+}
+
+fn gpg_sign(buffer: &[u8], key: &str) -> JoshResult<Vec<u8>> {
+    use std::io::Write;
+    let mut gpg = std::process::Command::new("gpg")
+        .args(&["--batch", "--detach-sign", "--armor", "--local-user", key])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()?;
+    gpg.stdin
+        .take()
+        .ok_or(josh_error("gpg: no stdin"))?
+        .write_all(buffer)?;
+    let output = gpg.wait_with_output()?;
+    if !output.status.success() {
+        return Err(josh_error(&format!("gpg: can't sign with key {:?}", key)));
+    }
+    return Ok(output.stdout);
+}
+
+/// Notes of a notes tree, by the id of the annotated object. Notes trees can use
+/// subdirectories ("fanout") named by the first characters of the id.
+fn notes_of(repo: &git2::Repository, tree: git2::Oid) -> JoshResult<Vec<(git2::Oid, git2::Oid)>> {
+    let mut notes = vec![];
+    if let Ok(tree) = repo.find_tree(tree) {
+        tree.walk(git2::TreeWalkMode::PreOrder, |root, entry| {
+            let name = format!("{}{}", root.replace("/", ""), entry.name().unwrap_or(""));
+            if entry.kind() == Some(git2::ObjectType::Blob) {
+                if let Ok(oid) = git2::Oid::from_str(&name) {
+                    notes.push((oid, entry.id()));
+                }
+            }
+            0
+        })?;
+    }
+    return Ok(notes);
+}
+
+fn notes_tree(repo: &git2::Repository, notes: &[(git2::Oid, git2::Oid)]) -> JoshResult<git2::Oid> {
+    let mut builder = repo.treebuilder(None)?;
+    for (oid, note) in notes {
+        builder.insert(oid.to_string(), *note, 0o0100644)?;
+    }
+    return Ok(builder.write()?);
+}
+
+/// Check if `commit` is the commit that `filtered` was created from, as opposed to a
+/// commit that did not change the filtered content.
+fn is_original(
+    transaction: &cache::Transaction,
+    filter: filter::Filter,
+    commit: &git2::Commit,
+    filtered: git2::Oid,
+) -> JoshResult<bool> {
+    for parent in commit.parents() {
+        if filter::apply_to_commit(filter, &parent, transaction)? == filtered {
+            return Ok(false);
+        }
+    }
+    return Ok(true);
+}
+
+/// Filter the notes commit `notes`: Notes attached to commits are attached to the
+/// corresponding filtered commits instead. Notes of commits that don't change the
+/// filtered content or are not part of the filtered history are dropped.
+/// The result has no parents, so the notes history is not kept.
+pub fn filter_notes(
+    transaction: &cache::Transaction,
+    filter: filter::Filter,
+    notes: git2::Oid,
+) -> JoshResult<git2::Oid> {
+    if let Some(filtered) = transaction.get_notes(filter, notes) {
+        return Ok(filtered);
+    }
+    let repo = transaction.repo();
+    let notes_commit = repo.find_commit(notes)?;
+
+    // The result changes once missing commits are fetched, so it is only cached if none are
+    let mut complete = true;
+    let mut filtered_notes = vec![];
+    for (oid, note) in notes_of(&repo, notes_commit.tree_id())? {
+        let commit = ok_or!(repo.find_commit(oid), {
+            complete = false;
+            continue;
+        });
+        let filtered = filter::apply_to_commit(filter, &commit, transaction)?;
+        complete = complete && filter::is_complete(transaction, filter, oid);
+        if filtered != git2::Oid::zero() && is_original(transaction, filter, &commit, filtered)? {
+            filtered_notes.push((filtered, note));
+        }
+    }
+    let tree = repo.find_tree(notes_tree(&repo, &filtered_notes)?)?;
+    let filtered = rewrite_commit(&repo, &notes_commit, &[], &tree, None)?;
+    if complete {
+        transaction.insert_notes(filter, notes, filtered);
+    }
+    return Ok(filtered);
+}
+
+/// Inverse of `filter_notes`: Apply the changes to notes between the filtered notes
+/// commits `old` and `new` to the notes commit `original_notes` (which can be zero if
+/// there are no notes yet). Filtered commits are looked up in the history of `contained_in`.
+pub fn unapply_notes(
+    transaction: &cache::Transaction,
+    filter: filter::Filter,
+    original_notes: git2::Oid,
+    old: git2::Oid,
+    new: git2::Oid,
+    contained_in: &[git2::Oid],
+) -> JoshResult<git2::Oid> {
+    let repo = transaction.repo();
+    let tree_of = |oid| -> JoshResult<git2::Oid> {
+        Ok(if oid == git2::Oid::zero() {
+            filter::tree::empty_id()
+        } else {
+            repo.find_commit(oid)?.tree_id()
+        })
+    };
+    let old_notes: std::collections::HashMap<_, _> =
+        notes_of(&repo, tree_of(old)?)?.into_iter().collect();
+    let new_notes: std::collections::HashMap<_, _> =
+        notes_of(&repo, tree_of(new)?)?.into_iter().collect();
+    let mut notes: std::collections::BTreeMap<_, _> = notes_of(&repo, tree_of(original_notes)?)?
+        .into_iter()
+        .collect();
+
+    let mut changed: Vec<_> = old_notes
+        .keys()
+        .chain(new_notes.keys())
+        .filter(|x| old_notes.get(x) != new_notes.get(x))
+        .cloned()
+        .collect();
+    changed.sort();
+    changed.dedup();
+
+    for filtered in changed {
+        let mut original = git2::Oid::zero();
+        for c in contained_in {
+            original = find_original(transaction, filter, *c, filtered)?;
+            if original != git2::Oid::zero() {
+                break;
+            }
+        }
+        if original == git2::Oid::zero() {
+            return Err(josh_error(&format!(
+                "can't find the original of {} to attach the note to",
+                filtered
+            )));
+        }
+        if let Some(note) = new_notes.get(&filtered) {
+            notes.insert(original, *note);
+        } else {
+            notes.remove(&original);
+        }
+    }
+
+    let notes: Vec<_> = notes.into_iter().collect();
+    let tree = repo.find_tree(notes_tree(&repo, &notes)?)?;
+    let new_commit = repo.find_commit(new)?;
+    let parents = if original_notes == git2::Oid::zero() {
+        vec![]
+    } else {
+        vec![repo.find_commit(original_notes)?]
+    };
     let b = repo.commit_create_buffer(
-        &base.author(),
-        &base.committer(),
-        &base.message_raw().unwrap_or("no message"),
-        tree,
-        parents,
+        &new_commit.author(),
+        &new_commit.committer(),
+        &String::from_utf8_lossy(new_commit.message_bytes()),
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
     )?;
-
     return Ok(repo.odb()?.write(git2::ObjectType::Commit, &b)?);
 }
 
@@ -237,6 +506,17 @@ pub fn unapply_filter(
             continue;
         }
 
+        // With `:SQUASH` root commits can be filtered versions of upstream commits that
+        // are not part of the filtered history of `original_target`, like the result for
+        // an older commit
+        if module_commit.parent_count() == 0
+            && filter::contains_squash(filterobj)
+            && find_unapply_base(&transaction, &mut bm, filterobj, original_target, rev)?
+                != git2::Oid::zero()
+        {
+            continue;
+        }
+
         let mut filtered_parent_ids: Vec<_> = module_commit.parent_ids().collect();
 
         let is_initial_merge = filtered_parent_ids.len() == 2
@@ -281,6 +561,42 @@ pub fn unapply_filter(
 
         let commit_message = module_commit.summary().unwrap_or("NO COMMIT MESSAGE");
 
+        for parent in module_commit.parents() {
+            if let Err(JoshError(msg)) = filter::check_repo_changes(
+                &transaction.repo(),
+                filterobj,
+                &parent.tree()?,
+                &tree,
+                &original_parents_refs
+                    .get(0)
+                    .map(|x| x.tree())
+                    .transpose()?
+                    .unwrap_or(filter::tree::empty(&transaction.repo())),
+            ) {
+                return Err(josh_error(&format!(
+                    "\nCan't apply {:?} ({:?})\n{}",
+                    commit_message,
+                    module_commit.id(),
+                    msg
+                )));
+            }
+        }
+
+        let mut meta = filter::unapply_meta(
+            transaction,
+            filterobj,
+            CommitMeta::from_commit(&module_commit),
+            original_parents_refs
+                .get(0)
+                .map(|x| x.tree())
+                .transpose()?
+                .unwrap_or(filter::tree::empty(&transaction.repo())),
+        )?;
+
+        if let Some(origin) = transaction.origin() {
+            meta.message = strip_origin_trailer(&meta.message, origin);
+        }
+
         let new_trees: JoshResult<std::collections::HashSet<_>> = {
             let s = tracing::span!(
                 tracing::Level::TRACE,
@@ -294,7 +610,14 @@ pub fn unapply_filter(
             original_parents_refs
                 .iter()
                 .map(|x| -> JoshResult<_> {
-                    Ok(filter::unapply(transaction, filterobj, tree.clone(), x.tree()?)?.id())
+                    Ok(filter::unapply_for_commit(
+                        transaction,
+                        filterobj,
+                        tree.clone(),
+                        x.tree()?,
+                        &meta,
+                    )?
+                    .id())
                 })
                 .collect()
         };
@@ -319,11 +642,12 @@ pub fn unapply_filter(
                 tracing::debug!("unrelated history");
                 // 0 means the history is unrelated. Pushing it will fail if we are not
                 // dealing with either a force push or a push with the "merge" option set.
-                filter::unapply(
+                filter::unapply_for_commit(
                     transaction,
                     filterobj,
                     tree,
                     filter::tree::empty(&transaction.repo()),
+                    &meta,
                 )?
             }
             parent_count => {
@@ -340,6 +664,7 @@ pub fn unapply_filter(
             &module_commit,
             &original_parents_refs,
             &new_tree,
+            Some(&meta),
         )?;
 
         if let Some(id) = super::get_change_id(&module_commit) {
@@ -366,6 +691,7 @@ pub fn unapply_filter(
                     &module_commit,
                     &original_parents_refs,
                     &transaction.repo().find_tree(merged_tree)?,
+                    Some(&meta),
                 )?;
             }
         }
@@ -403,12 +729,14 @@ pub fn create_filtered_commit<'a>(
     filtered_tree: git2::Tree<'a>,
     transaction: &cache::Transaction,
     filter: filter::Filter,
+    meta: Option<CommitMeta>,
 ) -> JoshResult<git2::Oid> {
     let (r, is_new) = create_filtered_commit2(
         &transaction.repo(),
         original_commit,
         filtered_parent_ids,
         filtered_tree,
+        filtered_meta(original_commit, transaction, meta),
     )?;
 
     let store = is_new || original_commit.parent_ids().len() != 1;
@@ -418,11 +746,78 @@ pub fn create_filtered_commit<'a>(
     return Ok(r);
 }
 
+/// Create the root commit with the tree of `original_commit` that `:SQUASH` turns it into
+pub fn create_squashed_commit(
+    original_commit: &git2::Commit,
+    transaction: &cache::Transaction,
+) -> JoshResult<git2::Oid> {
+    rewrite_commit(
+        &transaction.repo(),
+        original_commit,
+        &[],
+        &original_commit.tree()?,
+        filtered_meta(original_commit, transaction, None).as_ref(),
+    )
+}
+
+/// Metadata of the commit created from `original_commit`, with the origin trailer
+/// added if the transaction has an origin
+fn filtered_meta(
+    original_commit: &git2::Commit,
+    transaction: &cache::Transaction,
+    meta: Option<CommitMeta>,
+) -> Option<CommitMeta> {
+    if let Some(origin) = transaction.origin() {
+        let mut meta = meta.unwrap_or_else(|| CommitMeta::from_commit(original_commit));
+        meta.message = add_origin_trailer(&meta.message, origin, original_commit.id());
+        Some(meta)
+    } else {
+        meta
+    }
+}
+
+const ORIGIN_TRAILER: &str = "Josh-Origin: ";
+
+/// Append a trailer naming the commit `sha` of the repository `origin` to `message`.
+/// Messages that already have a trailer for `origin` are kept, so when several filters
+/// are applied after each other the trailer names the commit they were first applied to.
+fn add_origin_trailer(message: &str, origin: &str, sha: git2::Oid) -> String {
+    let prefix = format!("{}{}@", ORIGIN_TRAILER, origin);
+    if message.lines().any(|l| l.starts_with(&prefix)) {
+        return message.to_string();
+    }
+
+    let message = message.trim_end();
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or("");
+    let has_trailers = message.contains("\n\n")
+        && last_paragraph.lines().all(|l| {
+            l.find(": ")
+                .map(|i| i > 0 && !l[..i].contains(char::is_whitespace))
+                .unwrap_or(false)
+        });
+    let separator = if has_trailers { "\n" } else { "\n\n" };
+    return format!("{}{}{}{}\n", message, separator, prefix, sha);
+}
+
+/// Remove the trailers added by `add_origin_trailer` for `origin` from `message`
+fn strip_origin_trailer(message: &str, origin: &str) -> String {
+    let prefix = format!("{}{}@", ORIGIN_TRAILER, origin);
+    if !message.lines().any(|l| l.starts_with(&prefix)) {
+        return message.to_string();
+    }
+    let lines: Vec<_> = message
+        .lines()
+        .filter(|l| !l.starts_with(&prefix))
+        .collect();
+    return format!("{}\n", lines.join("\n").trim_end());
+}
+
 fn create_filtered_commit2<'a>(
     repo: &'a git2::Repository,
     original_commmit: &'a git2::Commit,
     filtered_parent_ids: Vec<git2::Oid>,
     filtered_tree: git2::Tree<'a>,
+    meta: Option<CommitMeta>,
 ) -> JoshResult<(git2::Oid, bool)> {
     let filtered_parent_commits: std::result::Result<Vec<_>, _> = filtered_parent_ids
         .iter()
@@ -468,6 +863,7 @@ fn create_filtered_commit2<'a>(
             &original_commmit,
             &selected_filtered_parent_commits,
             &filtered_tree,
+            meta.as_ref(),
         )?,
         true,
     ));
@@ -7,20 +7,29 @@ use tracing::{info, span, Level};
 
 pub type KnownViews = BTreeMap<String, BTreeSet<String>>;
 
+/// Pairs of upstream refs and the refs in `namespace` their filtered versions are
+/// stored in. With `notes` set notes refs are included.
 pub fn default_from_to(
     repo: &git2::Repository,
     namespace: &str,
     upstream_repo: &str,
     filter_spec: &str,
+    notes: bool,
 ) -> Vec<(String, String)> {
     let mut refs = vec![];
 
-    for glob in [
+    let mut globs = vec![
         format!("refs/josh/upstream/{}/refs/heads/*", &to_ns(upstream_repo)),
         format!("refs/josh/upstream/{}/refs/tags/*", &to_ns(upstream_repo)),
-    ]
-    .iter()
-    {
+    ];
+    if notes {
+        globs.push(format!(
+            "refs/josh/upstream/{}/refs/notes/*",
+            &to_ns(upstream_repo)
+        ));
+    }
+
+    for glob in globs.iter() {
         for refname in repo.references_glob(glob).unwrap().names() {
             let refname = refname.unwrap();
             let to_ref = refname.replacen("refs/josh/upstream", "refs/namespaces", 1);
@@ -154,6 +163,95 @@ pub fn find_all_workspaces_and_subdirectories(
     return Ok(hs);
 }
 
+/// Find the workspaces whose filtered tree differs between `old` and `new`, looking for
+/// `workspace.josh` files in both trees. Returns the paths of the workspace directories.
+pub fn affected_workspaces(
+    transaction: &cache::Transaction,
+    old: &git2::Tree,
+    new: &git2::Tree,
+) -> JoshResult<Vec<std::path::PathBuf>> {
+    let _trace_s = span!(Level::TRACE, "affected_workspaces");
+    let repo = transaction.repo();
+
+    let mut changed = vec![];
+    repo.diff_tree_to_tree(Some(old), Some(new), None)?
+        .foreach(
+            &mut |delta, _| {
+                changed.extend(delta.old_file().path().map(|x| x.to_owned()));
+                changed.extend(delta.new_file().path().map(|x| x.to_owned()));
+                true
+            },
+            None,
+            None,
+            None,
+        )?;
+
+    let mut workspaces = BTreeSet::new();
+    for tree in [old, new].iter() {
+        workspaces.extend(
+            find_all_workspaces_and_subdirectories(tree)?
+                .into_iter()
+                .filter(|x| x.starts_with(":workspace=")),
+        );
+    }
+
+    let mut result = vec![];
+    for spec in workspaces {
+        let path = Path::new(spec.trim_start_matches(":workspace="));
+        let reads_changes = [old, new].iter().any(|tree| {
+            let ws = filter::parse(&filter::tree::get_blob(
+                &repo,
+                tree,
+                &path.join("workspace.josh"),
+            ))
+            .unwrap_or(filter::nop());
+            let base =
+                filter::parse(&format!(":/{}", path.to_string_lossy())).unwrap_or(filter::nop());
+            let patterns = filter::footprint(filter::compose(base, ws));
+            changed
+                .iter()
+                .any(|x| filter::footprint_matches(&patterns, x))
+        });
+        if !reads_changes {
+            continue;
+        }
+
+        let filter = filter::parse(&spec)?;
+        if filter::apply(transaction, filter, old.clone())?.id()
+            != filter::apply(transaction, filter, new.clone())?.id()
+        {
+            result.push(path.to_owned());
+        }
+    }
+    return Ok(result);
+}
+
+/// Find the workspaces of `tree` that include the file `path` and the path it has inside
+/// of each of them. Returns pairs of workspace directory and path inside the workspace.
+pub fn workspaces_including(
+    transaction: &cache::Transaction,
+    tree: &git2::Tree,
+    path: &Path,
+) -> JoshResult<Vec<(std::path::PathBuf, std::path::PathBuf)>> {
+    let _trace_s = span!(Level::TRACE, "workspaces_including");
+
+    let mut result = vec![];
+    for spec in find_all_workspaces_and_subdirectories(tree)? {
+        if !spec.starts_with(":workspace=") {
+            continue;
+        }
+        let filter = filter::parse(&spec)?;
+        if let Some(dst) = filter::tree::mapped_path(transaction, filter, tree.clone(), path)? {
+            result.push((
+                Path::new(spec.trim_start_matches(":workspace=")).to_owned(),
+                dst,
+            ));
+        }
+    }
+    result.sort();
+    return Ok(result);
+}
+
 pub fn get_info(
     transaction: &cache::Transaction,
     filter: filter::Filter,
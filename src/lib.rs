@@ -162,8 +162,10 @@ pub fn get_change_id(commit: &git2::Commit) -> Option<String> {
     return None;
 }
 
+/// Update `to_refname` with the result of applying the filter to `from_refsname`.
+/// Unlike `filter_refs` errors are returned to the caller.
 #[tracing::instrument(skip(transaction))]
-fn filter_ref(
+pub fn filter_ref(
     transaction: &cache::Transaction,
     filterobj: filter::Filter,
     from_refsname: &str,
@@ -174,13 +176,39 @@ fn filter_ref(
         let original_commit = reference.peel_to_commit()?;
         let oid = original_commit.id();
 
+        // Notes refs point to commits with notes trees, not to commits that can be filtered
+        let is_notes_ref =
+            from_refsname.starts_with("refs/notes/") || from_refsname.contains("/refs/notes/");
+
         let filter_commit = if let Some(s) = transaction.get_ref(filterobj, oid) {
             s
+        } else if is_notes_ref {
+            history::filter_notes(&transaction, filterobj, oid)?
         } else {
             tracing::trace!("apply_to_commit");
 
             filter::apply_to_commit(filterobj, &original_commit, &transaction)?
         };
+        if filter::is_complete(&transaction, filterobj, oid) {
+            transaction.insert_ref(filterobj, oid, filter_commit);
+        }
+
+        // Annotated tags get a filtered tag object instead of pointing to the commit directly
+        let is_tag_ref = to_refname.starts_with("refs/tags/") || to_refname.contains("/refs/tags/");
+        let target = match reference.as_tag() {
+            Some(tag) if is_tag_ref && filter_commit != git2::Oid::zero() => {
+                if let Some(filtered) = transaction.get_tag(filterobj, tag.id()) {
+                    filtered
+                } else {
+                    let filtered = history::rewrite_tag(&transaction.repo(), tag, filter_commit)?;
+                    if filter::is_complete(&transaction, filterobj, oid) {
+                        transaction.insert_tag(filterobj, tag.id(), filtered);
+                    }
+                    filtered
+                }
+            }
+            _ => filter_commit,
+        };
 
         let previous = transaction
             .repo()
@@ -188,31 +216,29 @@ fn filter_ref(
             .map(|x| x.id())
             .unwrap_or(git2::Oid::zero());
 
-        if filter_commit != previous {
+        if target != previous {
             updated_count += 1;
             tracing::trace!(
                 "filter_ref: update reference: {:?} -> {:?}, target: {:?}, filter: {:?}",
                 &from_refsname,
                 &to_refname,
-                filter_commit,
+                target,
                 &filter::spec(filterobj),
             );
         }
 
-        transaction.insert_ref(filterobj, oid, filter_commit);
-
-        if filter_commit != git2::Oid::zero() {
+        if target != git2::Oid::zero() {
             ok_or!(
                 transaction
                     .repo()
-                    .reference(&to_refname, filter_commit, true, "apply_filter")
+                    .reference(&to_refname, target, true, "apply_filter")
                     .map(|_| ()),
                 {
                     tracing::error!(
                         "can't update reference: {:?} -> {:?}, target: {:?}, filter: {:?}",
                         &from_refsname,
                         &to_refname,
-                        filter_commit,
+                        target,
                         &filter::spec(filterobj),
                     );
                 }
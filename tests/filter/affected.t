@@ -0,0 +1,53 @@
+  $ git init -q 1> /dev/null
+
+  $ mkdir -p lib1 lib2 app1 app2 ws1 ws2
+  $ echo contents1 > lib1/file1
+  $ echo contents1 > lib2/file1
+  $ echo contents1 > app1/file1
+  $ echo contents1 > app2/file1
+  $ cat > ws1/workspace.josh <<EOF
+  > lib = :/lib1
+  > EOF
+  $ cat > ws2/workspace.josh <<EOF
+  > lib = :/lib2
+  > app = :/app2:exclude[::*.md]
+  > EOF
+  $ git add .
+  $ git commit -m "initial" 1> /dev/null
+  $ git tag initial
+
+Changes to files mapped into a workspace or to the workspace itself affect it
+
+  $ echo contents2 > lib1/file1
+  $ git commit -a -m "edit lib1" 1> /dev/null
+  $ josh-filter --affected initial
+  ws1/workspace.josh
+
+  $ echo contents2 > ws2/file2
+  $ git add ws2
+  $ git commit -m "add file in ws2" 1> /dev/null
+  $ josh-filter --affected HEAD~1
+  ws2/workspace.josh
+
+  $ josh-filter --affected initial
+  ws1/workspace.josh
+  ws2/workspace.josh
+
+Changes to files that are not mapped or excluded by the filter don't
+
+  $ echo contents2 > app1/file1
+  $ echo contents2 > app2/README.md
+  $ git add .
+  $ git commit -m "edit unmapped files" 1> /dev/null
+  $ josh-filter --affected HEAD~1
+
+Workspaces can be added and removed
+
+  $ mkdir ws3
+  $ echo ":/app1" > ws3/workspace.josh
+  $ git add ws3
+  $ git rm -q -r ws1
+  $ git commit -m "add ws3, remove ws1" 1> /dev/null
+  $ josh-filter --affected HEAD~1
+  ws1/workspace.josh
+  ws3/workspace.josh
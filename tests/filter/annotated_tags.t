@@ -0,0 +1,60 @@
+  $ git init -q 1> /dev/null
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ echo contents1 > sub2/file2
+  $ git add .
+  $ git commit -m "initial" 1> /dev/null
+  $ git tag -a v1.0 -m "Release 1.0" -m "With some notes"
+  $ git tag v1.0-light
+
+Annotated tags are filtered into tag objects with the same tagger and message
+
+  $ josh-filter :/sub1 refs/tags/v1.0 --update refs/tags/filtered
+  $ git cat-file -t filtered
+  tag
+  $ git cat-file -p filtered | sed 's/^object .*/object */'
+  object *
+  type commit
+  tag v1.0
+  tagger Josh <josh@example.com> 1112911993 +0000
+  
+  Release 1.0
+  
+  With some notes
+  $ git ls-tree --name-only filtered
+  file1
+
+  $ josh-filter :/sub1 refs/tags/v1.0-light --update refs/tags/filtered-light
+  $ git cat-file -t filtered-light
+  commit
+
+Other refs point to the filtered commit
+
+  $ josh-filter :/sub1 refs/tags/v1.0
+  $ git cat-file -t FILTERED_HEAD
+  commit
+
+Signatures of the original tag are removed
+
+  $ git cat-file tag v1.0 > tag
+  $ cat >> tag <<EOF
+  > -----BEGIN PGP SIGNATURE-----
+  > 
+  > not a real signature
+  > -----END PGP SIGNATURE-----
+  > EOF
+  $ git update-ref refs/tags/signed $(git hash-object -t tag -w tag)
+  $ josh-filter :/sub2 refs/tags/signed --update refs/tags/filtered-signed
+  $ git cat-file -p filtered-signed | tail -3
+  Release 1.0
+  
+  With some notes
+
+Filtered tags are cached apart from the filtered commits
+
+  $ git clone -q . fresh
+  $ cd fresh
+  $ josh-filter -s :/sub2 refs/tags/signed --update refs/tags/filtered-signed
+  [1] :/sub2
+  [1] _tags
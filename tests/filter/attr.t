@@ -0,0 +1,107 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p src/internal docs
+  $ echo contents > src/a.c
+  $ echo contents > src/secret.c
+  $ echo contents > src/internal/b.c
+  $ echo contents > docs/README
+  $ echo contents > docs/notes.private
+  $ cat > .gitattributes <<EOF
+  > *.private josh-private
+  > /release.sh export-ignore
+  > EOF
+  $ cat > src/.gitattributes <<EOF
+  > internal/** josh-private
+  > secret.c josh-private
+  > EOF
+  $ echo contents > release.sh
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -p ':attr=josh-private'
+  :attr=josh-private
+
+  $ josh-filter -s ':attr=josh-private' master --update refs/heads/filtered
+  [1] :attr=josh-private
+  $ git ls-tree -r --name-only filtered
+  .gitattributes
+  docs/README
+  release.sh
+  src/.gitattributes
+  src/a.c
+
+  $ josh-filter ':attr=export-ignore' master --update refs/heads/export
+  $ git ls-tree -r --name-only export
+  .gitattributes
+  docs/README
+  docs/notes.private
+  src/.gitattributes
+  src/a.c
+  src/internal/b.c
+  src/secret.c
+
+  $ cat > src/.gitattributes <<EOF
+  > internal/** josh-private
+  > EOF
+  $ git commit -am "publish secret.c" 1> /dev/null
+  $ josh-filter ':attr=josh-private' master --update refs/heads/filtered
+  $ git ls-tree -r --name-only filtered
+  .gitattributes
+  docs/README
+  release.sh
+  src/.gitattributes
+  src/a.c
+  src/secret.c
+
+  $ git checkout -q filtered
+  $ echo more >> src/a.c
+  $ git rm -q docs/README
+  $ git commit -am "edit" 1> /dev/null
+  $ josh-filter ':attr=josh-private' --reverse master --update refs/heads/filtered
+  $ git checkout -q master
+  $ git ls-tree -r --name-only master
+  .gitattributes
+  docs/notes.private
+  release.sh
+  src/.gitattributes
+  src/a.c
+  src/internal/b.c
+  src/secret.c
+  $ cat src/a.c
+  contents
+  more
+
+Values set the attribute, "-" unsets it and "!" makes it unspecified again
+
+  $ cat > src/.gitattributes <<EOF
+  > *.c josh-private=yes
+  > a.c -josh-private
+  > secret.c !josh-private
+  > EOF
+  $ git commit -qam "attributes with values"
+  $ josh-filter ':attr=josh-private' master --update refs/heads/values
+  $ git ls-tree -r --name-only values
+  .gitattributes
+  release.sh
+  src/.gitattributes
+  src/a.c
+  src/secret.c
+
+Patterns matching directories don't apply to the files in them
+
+  $ cat > src/.gitattributes <<EOF
+  > internal josh-private
+  > EOF
+  $ git commit -qam "directory pattern"
+  $ josh-filter ':attr=josh-private' master --update refs/heads/dir
+  $ git ls-tree -r --name-only dir
+  .gitattributes
+  release.sh
+  src/.gitattributes
+  src/a.c
+  src/internal/b.c
+  src/secret.c
@@ -0,0 +1,71 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ cat > .mailmap <<EOF
+  > Partner Team <team@partner.com> <alice@internal.com>
+  > Partner Team <team@partner.com> <bob@internal.com>
+  > Release Bot <bot@partner.com> Build Server <ci@internal.com>
+  > EOF
+  $ mkdir sub1
+  $ echo contents1 > sub1/file1
+  $ git add .
+  $ GIT_AUTHOR_NAME=Alice GIT_AUTHOR_EMAIL=alice@internal.com git commit -m "add file1" 1> /dev/null
+  $ echo contents2 > sub1/file2
+  $ git add sub1
+  $ GIT_AUTHOR_NAME=Bob GIT_AUTHOR_EMAIL=bob@internal.com git commit -m "add file2" 1> /dev/null
+  $ echo contents3 > sub1/file3
+  $ git add sub1
+  $ GIT_COMMITTER_NAME="Build Server" GIT_COMMITTER_EMAIL=ci@internal.com git commit -m "add file3" 1> /dev/null
+
+  $ git log --pretty="%an <%ae> / %cn <%ce> %s"
+  Josh <josh@example.com> / Build Server <ci@internal.com> add file3
+  Bob <bob@internal.com> / Josh <josh@example.com> add file2
+  Alice <alice@internal.com> / Josh <josh@example.com> add file1
+
+  $ josh-filter -s :authors=.mailmap:/sub1 master --update refs/heads/filtered
+  [3] :/sub1
+  [3] :authors=.mailmap
+  $ git log --pretty="%an <%ae> / %cn <%ce> %s" filtered
+  Josh <josh@example.com> / Release Bot <bot@partner.com> add file3
+  Partner Team <team@partner.com> / Josh <josh@example.com> add file2
+  Partner Team <team@partner.com> / Josh <josh@example.com> add file1
+
+  $ git checkout filtered 1> /dev/null
+  Switched to branch 'filtered'
+  $ echo contents4 > file4
+  $ git add file4
+  $ GIT_COMMITTER_NAME="Release Bot" GIT_COMMITTER_EMAIL=bot@partner.com git commit -m "add file4" 1> /dev/null
+  $ echo contents5 > file5
+  $ git add file5
+  $ GIT_AUTHOR_NAME="Partner Team" GIT_AUTHOR_EMAIL=team@partner.com git commit -m "add file5" 1> /dev/null
+  $ echo contents6 > file6
+  $ git add file6
+  $ GIT_AUTHOR_NAME=Carol GIT_AUTHOR_EMAIL=carol@partner.com git commit -m "add file6" 1> /dev/null
+
+  $ josh-filter -s :authors=.mailmap:/sub1 --reverse master --update refs/heads/filtered
+  [3] :/sub1
+  [3] :authors=.mailmap
+
+  $ git log --pretty="%an <%ae> / %cn <%ce> %s" master
+  Carol <carol@partner.com> / Josh <josh@example.com> add file6
+  Partner Team <team@partner.com> / Josh <josh@example.com> add file5
+  Josh <josh@example.com> / Build Server <ci@internal.com> add file4
+  Josh <josh@example.com> / Build Server <ci@internal.com> add file3
+  Bob <bob@internal.com> / Josh <josh@example.com> add file2
+  Alice <alice@internal.com> / Josh <josh@example.com> add file1
+
+  $ git checkout master 1> /dev/null
+  Switched to branch 'master'
+  $ tree sub1
+  sub1
+  |-- file1
+  |-- file2
+  |-- file3
+  |-- file4
+  |-- file5
+  `-- file6
+  
+  0 directories, 6 files
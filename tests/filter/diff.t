@@ -0,0 +1,60 @@
+  $ git init -q 1> /dev/null
+
+  $ mkdir -p a/b a/c d
+  $ echo contents1 > a/b/file1
+  $ echo contents1 > a/c/file2
+  $ echo contents1 > a/file3
+  $ echo contents1 > d/file4
+  $ git add .
+  $ git commit -m "initial" 1> /dev/null
+
+Filters that are written differently but produce the same tree are equivalent
+
+  $ josh-filter ':/a' --diff ':[::a/b/,::a/c/,::a/file3]:/a'
+  $ josh-filter ':/a' --diff ':[:/a:exclude[::c/],c=:/a/c]'
+  $ josh-filter ':/a:[x=:/b,y=:/c]' --diff ':[x=:/a/b,y=:/a/c]'
+
+Otherwise the differing files are listed
+
+  $ josh-filter ':/a' --diff ':/a:exclude[::b/]:prefix=x'
+  - b/file1
+  - c/file2
+  - file3
+  + x/c/file2
+  + x/file3
+  [1]
+  $ josh-filter ':[::a/,::d/]' --diff ':[::a/,e=:/d]'
+  - d/file4
+  + e/file4
+  [1]
+  $ josh-filter ':/a' --diff ':/a:replace="**/file?","contents1","contents2"'
+  M b/file1
+  M c/file2
+  M file3
+  [1]
+  $ josh-filter ':/a' --diff ':/a:['
+  ERROR: unexpected end of input
+   --> 1:6
+    |
+  1 | :/a:[
+    |      ^---
+    |
+    = expected filter or path
+  [1]
+
+The whole history can be compared
+
+  $ echo contents1 > d/file5
+  $ git add d
+  $ git commit -m "add file5" 1> /dev/null
+  $ echo contents2 > a/c/file2
+  $ git commit -a -m "edit file2" 1> /dev/null
+  $ josh-filter ':[::a/c/,::d/]' --diff ':[::a/c/,::d/file4]' --diff-history
+  * (glob)
+  - d/file5
+  * (glob)
+  - d/file5
+  [1]
+  $ josh-filter ':[::a/c/,::d/]' --diff ':[::a/c/,::d/file4]'
+  - d/file5
+  [1]
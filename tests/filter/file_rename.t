@@ -0,0 +1,58 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p tools sub1
+  $ echo "stages: [build]" > tools/ci.yml
+  $ echo contents1 > sub1/file1
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -p ::.gitlab-ci.yml=tools/ci.yml
+  ::.gitlab-ci.yml=tools/ci.yml
+  $ josh-filter -p :[::.gitlab-ci.yml=tools/ci.yml,::sub1/]
+  :[
+      ::sub1/
+      ::.gitlab-ci.yml=tools/ci.yml
+  ]
+  $ josh-filter ::a/=tools/ci.yml
+  ERROR: only single files can be renamed
+   --> 1:1
+    |
+  1 | ::a/=tools/ci.yml
+    | ^---------------^
+  [1]
+
+  $ josh-filter -s ::.gitlab-ci.yml=tools/ci.yml master --update refs/heads/filtered
+  [1] ::.gitlab-ci.yml=tools/ci.yml
+  $ git ls-tree -r --name-only filtered
+  .gitlab-ci.yml
+
+  $ mkdir ws
+  $ cat > ws/workspace.josh <<EOF
+  > ::.gitlab-ci.yml=tools/ci.yml
+  > lib = :/sub1
+  > EOF
+  $ git add ws
+  $ git commit -m "add ws" 1> /dev/null
+
+  $ josh-filter :workspace=ws master --update refs/heads/ws
+  $ git ls-tree -r --name-only ws
+  .gitlab-ci.yml
+  lib/file1
+  workspace.josh
+
+  $ git checkout -q ws
+  $ echo "stages: [build, test]" > .gitlab-ci.yml
+  $ git add .
+  $ git commit -m "edit ci" 1> /dev/null
+  $ josh-filter :workspace=ws --reverse master --update refs/heads/ws
+  $ git checkout -q master
+  $ cat tools/ci.yml
+  stages: [build, test]
+  $ git ls-tree -r --name-only master
+  sub1/file1
+  tools/ci.yml
+  ws/workspace.josh
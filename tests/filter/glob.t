@@ -0,0 +1,33 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p a/sub b/sub
+  $ echo contents > a/sub/file.txt
+  $ echo contents > a/sub/file.c
+  $ cp a/sub/* b/sub/
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+Both directories have the same tree, they are still filtered depending on their path
+
+  $ git ls-tree HEAD
+  040000 tree e9e3e26bbb9fbac2eada5dbfaf0760bbef881f30\ta (esc)
+  040000 tree e9e3e26bbb9fbac2eada5dbfaf0760bbef881f30\tb (esc)
+
+  $ josh-filter ::b/sub/*.txt master --update refs/heads/b
+  $ git ls-tree -r --name-only b
+  b/sub/file.txt
+  $ josh-filter ::a/sub/*.txt master --update refs/heads/a
+  $ git ls-tree -r --name-only a
+  a/sub/file.txt
+  $ josh-filter "::*/sub/*.txt" master --update refs/heads/both
+  $ git ls-tree -r --name-only both
+  a/sub/file.txt
+  b/sub/file.txt
+  $ josh-filter "::*/sub/*.c" master --update refs/heads/c
+  $ git ls-tree -r --name-only c
+  a/sub/file.c
+  b/sub/file.c
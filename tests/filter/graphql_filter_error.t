@@ -0,0 +1,54 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init repo 1> /dev/null
+  $ cd repo
+
+  $ mkdir good bad
+  $ cat > good/workspace.josh <<EOF
+  > a = :/sub1
+  > EOF
+  $ cat > bad/workspace.josh <<EOF
+  > a = :/sub1
+  > b = :/sub2:[x
+  > EOF
+  $ cat > query.graphql <<EOF
+  > query {
+  >  good: file(path: "good/workspace.josh") {
+  >   filterError {
+  >    message
+  >   }
+  >  }
+  >  bad: file(path: "bad/workspace.josh") {
+  >   filterError {
+  >    message
+  >    line
+  >    column
+  >    endLine
+  >    endColumn
+  >    expected
+  >   }
+  >  }
+  > }
+  > EOF
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -q "graphql=query.graphql"
+  {
+    "good": {
+      "filterError": null
+    },
+    "bad": {
+      "filterError": {
+        "message": "unexpected \"x\"",
+        "line": 2,
+        "column": 13,
+        "endLine": 2,
+        "endColumn": 13,
+        "expected": [
+          "filter"
+        ]
+      }
+    }
+  } (no-eol)
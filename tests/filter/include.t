@@ -0,0 +1,91 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p tools/compiler tools/linker libs/a shared ws
+  $ echo contents > tools/compiler/file
+  $ git add .
+  $ git commit -m "add compiler" 1> /dev/null
+  $ echo contents > tools/linker/file
+  $ git add .
+  $ git commit -m "add linker" 1> /dev/null
+  $ echo contents > libs/a/file
+  $ cat > shared/toolchain.josh <<EOF
+  > compiler = :/tools/compiler
+  > EOF
+  $ cat > ws/workspace.josh <<EOF
+  > a = :/libs/a
+  > :include=shared/toolchain.josh
+  > EOF
+  $ git add .
+  $ git commit -m "add ws" 1> /dev/null
+
+  $ josh-filter -p ':include=shared/toolchain.josh'
+  :include=shared/toolchain.josh
+
+  $ josh-filter :workspace=ws master --update refs/heads/filtered
+  $ git ls-tree -r --name-only filtered
+  a/file
+  compiler/file
+  workspace.josh
+
+  $ josh-filter :include=shared/toolchain.josh master --update refs/heads/toolchain
+  $ git ls-tree -r --name-only toolchain
+  compiler/file
+
+Changing the included file adds the history of the new paths
+
+  $ cat >> shared/toolchain.josh <<EOF
+  > linker = :/tools/linker
+  > EOF
+  $ git commit -am "add linker to toolchain" 1> /dev/null
+
+  $ josh-filter :workspace=ws master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  *   add linker to toolchain
+  |\  
+  | * add linker
+  * add ws
+  * add compiler
+  $ git ls-tree -r --name-only filtered
+  a/file
+  compiler/file
+  linker/file
+  workspace.josh
+
+  $ josh-filter :include=shared/toolchain.josh master --update refs/heads/toolchain
+  $ git log --graph --pretty=%s toolchain
+  *   add linker to toolchain
+  |\  
+  | * add linker
+  * add compiler
+
+Pushing uses the included file from upstream
+
+  $ git checkout -q filtered
+  $ echo more >> linker/file
+  $ git commit -am "edit linker" 1> /dev/null
+  $ josh-filter :workspace=ws --reverse master --update refs/heads/filtered
+  $ git checkout -q master
+  $ cat tools/linker/file
+  contents
+  more
+
+Cycles are detected and reported, the filtered reference is not updated
+
+  $ cat > shared/cycle1.josh <<EOF
+  > :include=shared/cycle2.josh
+  > EOF
+  $ cat > shared/cycle2.josh <<EOF
+  > ::libs/
+  > :include=shared/cycle1.josh
+  > EOF
+  $ git add .
+  $ git commit -m "add cycle" 1> /dev/null
+  $ josh-filter :include=shared/cycle1.josh master --update refs/heads/cycle
+  ERROR: include cycle: shared/cycle1.josh -> shared/cycle2.josh -> shared/cycle1.josh
+  [1]
+  $ git rev-parse -q --verify cycle
+  [1]
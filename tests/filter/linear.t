@@ -0,0 +1,83 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir sub1
+  $ echo contents1 > sub1/file1
+  $ git add sub1
+  $ git commit -m "add file1" 1> /dev/null
+
+  $ git checkout -q -b feature
+  $ echo contents2 > sub1/file2
+  $ git add sub1
+  $ git commit -m "add file2" 1> /dev/null
+  $ mkdir sub2
+  $ echo contents3 > sub2/file3
+  $ git add sub2
+  $ git commit -m "add file3" 1> /dev/null
+
+  $ git checkout -q master
+  $ echo contents4 > sub1/file4
+  $ git add sub1
+  $ git commit -m "add file4" 1> /dev/null
+  $ git merge -q --no-ff feature -m "merge feature"
+  $ echo contents5 > sub1/file5
+  $ git add sub1
+  $ git commit -m "add file5" 1> /dev/null
+
+  $ git log --graph --pretty=%s
+  * add file5
+  *   merge feature
+  |\  
+  | * add file3
+  | * add file2
+  * | add file4
+  |/  
+  * add file1
+
+  $ josh-filter -s :linear master --update refs/heads/linear
+  [6] :linear
+  $ git log --graph --pretty=%s linear
+  * add file5
+  * merge feature
+  * add file4
+  * add file1
+  $ git ls-tree -r --name-only linear
+  sub1/file1
+  sub1/file2
+  sub1/file4
+  sub1/file5
+  sub2/file3
+  $ git diff master linear
+
+  $ josh-filter -s :/sub1:linear master --update refs/heads/sub1_linear
+  [5] :/sub1
+  [11] :linear
+  $ git log --graph --pretty=%s sub1_linear
+  * add file5
+  * merge feature
+  * add file4
+  * add file1
+  $ git ls-tree -r --name-only sub1_linear
+  file1
+  file2
+  file4
+  file5
+
+  $ git checkout -q linear
+  $ echo contents6 > sub2/file6
+  $ git add sub2
+  $ git commit -m "add file6" 1> /dev/null
+  $ josh-filter :linear --reverse master --update refs/heads/linear
+  $ git log --graph --pretty=%s master
+  * add file6
+  * add file5
+  *   merge feature
+  |\  
+  | * add file3
+  | * add file2
+  * | add file4
+  |/  
+  * add file1
@@ -0,0 +1,98 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p assets src
+  $ echo contents > src/a.c
+  $ head -c 2000 /dev/zero > assets/big.bin
+  $ head -c 2000 /dev/zero > assets/big2.bin
+  $ echo small > assets/small.bin
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -p ':maxsize=1000'
+  :maxsize=1000
+  $ josh-filter -p ':maxsize=1000,stub'
+  :maxsize=1000,stub
+
+  $ josh-filter -s ':maxsize=1000' master --update refs/heads/filtered
+  [1] :maxsize=1000
+  $ git ls-tree -r --name-only filtered
+  assets/small.bin
+  src/a.c
+
+  $ josh-filter ':maxsize=1000,stub' master --update refs/heads/stub
+  $ git ls-tree -r --name-only stub
+  assets/big.bin
+  assets/big2.bin
+  assets/small.bin
+  src/a.c
+  $ git show stub:assets/big.bin
+  File removed by josh
+  size: 2000
+  blob: * (glob)
+
+  $ git checkout -q filtered
+  $ echo more >> src/a.c
+  $ git add .
+  $ git commit -m "edit a.c" 1> /dev/null
+  $ josh-filter ':maxsize=1000' --reverse master --update refs/heads/filtered
+  $ git checkout -q master
+  $ git ls-tree -r --name-only master
+  assets/big.bin
+  assets/big2.bin
+  assets/small.bin
+  src/a.c
+  $ wc -c < assets/big.bin
+  2000
+  $ cat src/a.c
+  contents
+  more
+
+  $ git checkout -q stub
+  $ echo replaced > assets/big2.bin
+  $ echo more >> assets/small.bin
+  $ git add .
+  $ git commit -m "edit stub" 1> /dev/null
+  $ josh-filter ':maxsize=1000,stub' --reverse master --update refs/heads/stub
+  $ git checkout -q master
+  $ wc -c < assets/big.bin
+  2000
+  $ cat assets/big2.bin
+  replaced
+  $ cat assets/small.bin
+  small
+  more
+
+  $ josh-filter ':maxsize=large'
+  ERROR: invalid size "large"
+   --> 1:1
+    |
+  1 | :maxsize=large
+    | ^------------^
+  [1]
+
+Pushing files larger than the limit is rejected
+
+  $ git checkout -q filtered
+  $ head -c 2000 /dev/zero > large.bin
+  $ git add .
+  $ git commit -m "add large file" 1> /dev/null
+  $ josh-filter ':maxsize=1000' --reverse master --update refs/heads/filtered
+  ERROR: JoshError("\nCan't apply \"add large file\" (*)\nfilter not reversible: files larger than 1000 bytes: large.bin") (glob)
+  [1]
+  $ git checkout -q stub
+  $ head -c 2000 /dev/zero > assets/large.bin
+  $ git add .
+  $ git commit -m "add large stub file" 1> /dev/null
+  $ josh-filter ':maxsize=1000,stub' --reverse master --update refs/heads/stub
+  ERROR: JoshError("\nCan't apply \"add large stub file\" (*)\nfilter not reversible: files larger than 1000 bytes: assets/large.bin") (glob)
+  [1]
+  $ git checkout -q master
+  $ git ls-tree -r --name-only master
+  assets/big.bin
+  assets/big2.bin
+  assets/small.bin
+  src/a.c
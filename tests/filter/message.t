@@ -0,0 +1,113 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir sub1
+  $ echo contents1 > sub1/file1
+  $ git add sub1
+  $ git commit -m "add file1" -m "Details about file1" -m "Change-Id: I1234" 1> /dev/null
+  $ echo contents2 > sub1/file2
+  $ git add sub1
+  $ git commit -m "add file2" -m "Ticket: https://tickets.internal/42" 1> /dev/null
+
+  $ josh-filter -p ':/sub1:strip_message="^(Change-Id|Ticket): .*$":message="[sub1] {message}"'
+  :/sub1:strip_message="^(Change-Id|Ticket): .*$":message="[sub1] {message}"
+
+  $ josh-filter -s ':/sub1:strip_message="^(Change-Id|Ticket): .*$":message="[sub1] {message}"' master --update refs/heads/filtered
+  [2] :/sub1
+  [2] :message="[sub1] {message}"
+  [2] :strip_message="^(Change-Id|Ticket): .*$"
+  $ git log --pretty="%B---" filtered
+  [sub1] add file2
+  ---
+  [sub1] add file1
+  
+  Details about file1
+  ---
+
+  $ josh-filter ':message="{subject} (from monorepo)"' master --update refs/heads/subject
+  $ git log --pretty="%B---" subject
+  add file2 (from monorepo)
+  ---
+  add file1 (from monorepo)
+  ---
+
+  $ git checkout filtered 1> /dev/null
+  Switched to branch 'filtered'
+  $ echo contents3 > file3
+  $ git add file3
+  $ git commit -m "[sub1] add file3" 1> /dev/null
+
+  $ josh-filter ':/sub1:strip_message="^(Change-Id|Ticket): .*$":message="[sub1] {message}"' --reverse master --update refs/heads/filtered
+
+  $ git log --pretty="%B---" master
+  add file3
+  ---
+  add file2
+  
+  Ticket: https://tickets.internal/42
+  ---
+  add file1
+  
+  Details about file1
+  
+  Change-Id: I1234
+  ---
+
+  $ josh-filter -p ':message=":"'
+  :message=":"
+  $ josh-filter ':strip_message="("'
+  ERROR: invalid regex "("
+   --> 1:1
+    |
+  1 | :strip_message="("
+    | ^----------------^
+  [1]
+
+  $ josh-filter ':message="{message}(from monorepo)"' master --update refs/heads/suffix
+  $ git log -1 --pretty="%B---" suffix
+  add file3
+  (from monorepo)
+  ---
+  $ git checkout -q suffix
+  $ echo contents5 > sub1/file5
+  $ git add sub1
+  $ git commit -m "add file5" -m "(from monorepo)" 1> /dev/null
+  $ josh-filter ':message="{message}(from monorepo)"' --reverse master --update refs/heads/suffix
+  $ git log -2 --pretty="%B---" master
+  add file5
+  ---
+  add file3
+  ---
+
+Templates are checked when parsing
+
+  $ josh-filter ':message="{unknown}"'
+  ERROR: invalid template "{unknown}": KeyError(Invalid key: unknown)
+   --> 1:1
+    |
+  1 | :message="{unknown}"
+    | ^------------------^
+  [1]
+  $ josh-filter ':message="{message"'
+  ERROR: invalid template "{message": Invalid(Expected '}' before end of string)
+   --> 1:1
+    |
+  1 | :message="{message"
+    | ^-----------------^
+  [1]
+  $ josh-filter ':message="{{literal}} {subject}"' master --update refs/heads/braces
+  $ git log -1 --pretty="%B---" braces
+  {literal} add file5
+  ---
+
+Quotes in arguments are escaped with a backslash
+
+  $ josh-filter -p ':message="\"{subject}\""'
+  :message="\"{subject}\""
+  $ josh-filter ':message="\"{subject}\""' master --update refs/heads/quoted
+  $ git log -1 --pretty="%B---" quoted
+  "add file5"
+  ---
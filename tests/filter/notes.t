@@ -0,0 +1,62 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q 1> /dev/null
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ git add .
+  $ git commit -m "add file1" 1> /dev/null
+  $ echo contents2 > sub2/file2
+  $ git add .
+  $ git commit -m "add file2" 1> /dev/null
+  $ echo contents3 > sub1/file3
+  $ git add .
+  $ git commit -m "add file3" 1> /dev/null
+
+  $ git notes add -m "note on file1" HEAD~2
+  $ git notes add -m "note on file2" HEAD~1
+  $ git notes add -m "note on file3" HEAD
+
+Notes are attached to the filtered commits, notes of commits that don't
+change the filtered content are dropped
+
+  $ josh-filter :/sub1 refs/notes/commits --update refs/notes/filtered
+  $ josh-filter :/sub1 master --update refs/heads/filtered
+  $ git log --pretty="%s: %N" --notes=filtered filtered
+  add file3: note on file3
+  
+  add file1: note on file1
+  
+  $ git notes --ref filtered list | wc -l
+  2
+
+Changes to the filtered notes are mapped back to the original commits
+
+  $ git notes --ref filtered add -f -m "changed note" filtered
+  Overwriting existing notes for object * (glob)
+  $ git notes --ref filtered append -m "appended" filtered~1
+  $ josh-filter :/sub1 refs/notes/commits --update refs/notes/filtered --reverse
+  $ git log --pretty="%s: %N" --notes master
+  add file3: changed note
+  
+  add file2: note on file2
+  
+  add file1: note on file1
+  
+  appended
+  
+  $ git log --pretty="%s" refs/notes/commits
+  Notes added by 'git notes append'
+  Notes added by 'git notes add'
+  Notes added by 'git notes add'
+  Notes added by 'git notes add'
+
+Removed notes are removed from the original commits
+
+  $ josh-filter :/sub1 refs/notes/commits --update refs/notes/filtered
+  $ git notes --ref filtered remove filtered
+  Removing note for object filtered
+  $ josh-filter :/sub1 refs/notes/commits --update refs/notes/filtered --reverse
+  $ git notes list | wc -l
+  2
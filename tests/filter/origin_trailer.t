@@ -0,0 +1,90 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q 1> /dev/null
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ git add .
+  $ git commit -m "add file1" 1> /dev/null
+  $ echo contents2 > sub2/file2
+  $ git add .
+  $ git commit -m "add file2" 1> /dev/null
+  $ echo contents3 > sub1/file3
+  $ git add .
+  $ git commit -m "add file3" -m "Change-Id: 1234" 1> /dev/null
+
+Filtered commits name the commit they were created from
+
+  $ josh-filter :/sub1 --origin-trailer mono
+  $ git log --pretty="%B" FILTERED_HEAD | sed "s/$(git rev-parse HEAD)/HEAD/;s/$(git rev-parse HEAD~2)/HEAD~2/"
+  add file3
+  
+  Change-Id: 1234
+  Josh-Origin: mono@HEAD
+  
+  add file1
+  
+  Josh-Origin: mono@HEAD~2
+  
+
+The commit is the one of the input also when several filters are chained
+
+  $ josh-filter :/sub1:prefix=x --origin-trailer mono
+  $ git log --pretty="%(trailers:key=Josh-Origin,valueonly)" FILTERED_HEAD | sed "s/$(git rev-parse HEAD)/HEAD/;s/$(git rev-parse HEAD~2)/HEAD~2/"
+  mono@HEAD
+  
+  mono@HEAD~2
+  
+
+Squashed commits have the trailer as well
+
+  $ josh-filter --squash :/sub1 --origin-trailer mono
+  $ git log --pretty="%s %(trailers:key=Josh-Origin,valueonly)" FILTERED_HEAD | sed "s/$(git rev-parse HEAD)/HEAD/"
+  add file3 mono@HEAD
+  
+  $ josh-filter :SQUASH=$(git rev-parse HEAD~1):/sub1 --origin-trailer mono
+  $ git log --pretty="%s %(trailers:key=Josh-Origin,valueonly)" FILTERED_HEAD | sed "s/$(git rev-parse HEAD)/HEAD/;s/$(git rev-parse HEAD~1)/HEAD~1/"
+  add file3 mono@HEAD
+  
+  add file2 mono@HEAD~1
+  
+
+Without the option the filtered commits don't have the trailer
+
+  $ josh-filter :/sub1
+  $ git log --pretty="%s" FILTERED_HEAD
+  add file3
+  add file1
+  $ git log --pretty="%(trailers:key=Josh-Origin)" FILTERED_HEAD | grep Josh
+  [1]
+
+The trailer is removed from pushed commits
+
+  $ josh-filter :/sub1 --origin-trailer mono --update refs/heads/filtered
+  $ git checkout -q filtered
+  $ echo contents4 > file4
+  $ git add .
+  $ git commit -q --amend -m "$(git log -1 --pretty=%B)" -m "add file4"
+  $ git log -1 --pretty="%B" | sed "s/$(git rev-parse master)/master/"
+  add file3
+  
+  Change-Id: 1234
+  Josh-Origin: mono@master
+  
+  add file4
+  
+  $ git checkout -q master
+  $ josh-filter :/sub1 master --origin-trailer mono --update refs/heads/filtered --reverse
+  $ git log --pretty="%B" -1 master
+  add file3
+  
+  Change-Id: 1234
+  
+  add file4
+  
+  $ git ls-tree -r --name-only master
+  sub1/file1
+  sub1/file3
+  sub1/file4
+  sub2/file2
@@ -0,0 +1,91 @@
+Syntax errors point to the position where parsing failed
+
+  $ josh-filter ':/a[b'
+  ERROR: unexpected "[b"
+   --> 1:4
+    |
+  1 | :/a[b
+    |    ^---
+    |
+    = expected end of input or filter
+  [1]
+  $ josh-filter ':/a:[:/b,:/c'
+  ERROR: unexpected end of input
+   --> 1:13
+    |
+  1 | :/a:[:/b,:/c
+    |             ^---
+    |
+    = expected filter
+  [1]
+  $ josh-filter ':[a=:/b,c=:regex=(]'
+  ERROR: unexpected "(]"
+   --> 1:18
+    |
+  1 | :[a=:/b,c=:regex=(]
+    |                  ^---
+    |
+    = expected filter or argument
+  [1]
+
+Errors found after parsing point to the offending filter, also inside of groups
+
+  $ josh-filter ':/a:[x=:/b,y=:nosuch]'
+  ERROR: invalid filter
+   --> 1:14
+    |
+  1 | :/a:[x=:/b,y=:nosuch]
+    |              ^-----^
+  [1]
+  $ josh-filter ':subtract[:/a]'
+  ERROR: expected exactly two filters in :subtract
+   --> 1:11
+    |
+  1 | :subtract[:/a]
+    |           ^-^
+  [1]
+
+Positions in workspace files are given as line and column
+
+  $ cat > workspace.josh <<EOF
+  > # comment
+  > a = :/b
+  > c = :/d:[x
+  > EOF
+  $ josh-filter --file workspace.josh
+  ERROR: Invalid workspace:
+  ----
+  unexpected "x"
+   --> 3:10
+    |
+  3 | c = :/d:[x
+    |          ^---
+    |
+    = expected filter
+  
+  # comment
+  a = :/b
+  c = :/d:[x
+  
+  ----
+  [1]
+  $ cat > workspace.josh <<'EOF'
+  > $lib = :/lib
+  > a = $lib
+  > b = $other
+  > EOF
+  $ josh-filter --file workspace.josh
+  ERROR: Invalid workspace:
+  ----
+  undefined filter $other
+   --> 3:5
+    |
+  3 | b = $other
+    |     ^----^
+  
+  $lib = :/lib
+  a = $lib
+  b = $other
+  
+  ----
+  [1]
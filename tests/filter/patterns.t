@@ -0,0 +1,86 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p src/lib_a src/lib_b/test docs build
+  $ echo contents > src/lib_a/a.c
+  $ echo contents > src/lib_a/a.h
+  $ echo contents > src/lib_a/README.md
+  $ echo contents > src/lib_b/b.c
+  $ echo contents > src/lib_b/test/b_test.c
+  $ echo contents > docs/index.md
+  $ echo contents > build/out.c
+  $ echo contents > README.md
+  $ cat > export.list <<EOF
+  > # exported files
+  > src/**
+  > !*.md
+  > !test/
+  > /README.md
+  > EOF
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -p ':patterns="src/**","!*.md","!test/","/README.md"'
+  :patterns="src/**","!*.md","!test/","/README.md"
+
+  $ josh-filter ':patterns="src/**","!*.md","!test/","/README.md"' master --update refs/heads/inline
+  $ git ls-tree -r --name-only inline
+  README.md
+  src/lib_a/a.c
+  src/lib_a/a.h
+  src/lib_b/b.c
+
+  $ josh-filter ':patterns="build/","**/*.h"' master --update refs/heads/dirs
+  $ git ls-tree -r --name-only dirs
+  build/out.c
+  src/lib_a/a.h
+
+The stats are checked in a fresh clone, so they only list the cache of this filter
+
+  $ cd ${TESTTMP}
+  $ git clone -q real_repo file_repo
+  $ cd file_repo
+  $ josh-filter -s :patterns_file=export.list master --update refs/heads/file
+  [1] :patterns_file=export.list
+  $ git ls-tree -r --name-only file
+  README.md
+  src/lib_a/a.c
+  src/lib_a/a.h
+  src/lib_b/b.c
+
+  $ echo "docs/" >> export.list
+  $ git add export.list
+  $ git commit -m "export docs" 1> /dev/null
+  $ josh-filter -s :patterns_file=export.list master --update refs/heads/file
+  [2] :patterns_file=export.list
+  $ git ls-tree -r --name-only file
+  README.md
+  docs/index.md
+  src/lib_a/a.c
+  src/lib_a/a.h
+  src/lib_b/b.c
+
+  $ git checkout -q file
+  $ echo more >> src/lib_a/a.c
+  $ echo contents > src/lib_b/c.c
+  $ git rm -q src/lib_b/b.c
+  $ git add .
+  $ git commit -m "edit files" 1> /dev/null
+  $ josh-filter :patterns_file=export.list --reverse master --update refs/heads/file
+  $ git checkout -q master
+  $ git ls-tree -r --name-only master
+  README.md
+  build/out.c
+  docs/index.md
+  export.list
+  src/lib_a/README.md
+  src/lib_a/a.c
+  src/lib_a/a.h
+  src/lib_b/c.c
+  src/lib_b/test/b_test.c
+  $ cat src/lib_a/a.c
+  contents
+  more
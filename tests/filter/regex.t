@@ -0,0 +1,57 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p libs/lib_a libs/lib_b/sub libs/other lib_c
+  $ echo contents > libs/lib_a/BUILD
+  $ echo contents > libs/lib_a/a.c
+  $ echo contents > libs/lib_b/BUILD
+  $ echo contents > libs/lib_b/sub/BUILD
+  $ echo contents > libs/other/BUILD
+  $ echo contents > lib_c/BUILD
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -p ':regex="(.*/)?lib_[^/]*/BUILD"'
+  :regex="(.*/)?lib_[^/]*/BUILD"
+
+  $ josh-filter -s ':regex="(.*/)?lib_[^/]*/BUILD"' master --update refs/heads/filtered
+  [1] :regex="(.*/)?lib_[^/]*/BUILD"
+  $ git ls-tree -r --name-only filtered
+  lib_c/BUILD
+  libs/lib_a/BUILD
+  libs/lib_b/BUILD
+
+  $ josh-filter ':/libs:regex="lib_.*"' master --update refs/heads/libs
+  $ git ls-tree -r --name-only libs
+  lib_a/BUILD
+  lib_a/a.c
+  lib_b/BUILD
+  lib_b/sub/BUILD
+
+  $ git checkout -q filtered
+  $ echo more >> libs/lib_b/BUILD
+  $ git add .
+  $ git commit -m "edit BUILD" 1> /dev/null
+  $ josh-filter ':regex="(.*/)?lib_[^/]*/BUILD"' --reverse master --update refs/heads/filtered
+  $ git checkout -q master
+  $ git ls-tree -r --name-only master
+  lib_c/BUILD
+  libs/lib_a/BUILD
+  libs/lib_a/a.c
+  libs/lib_b/BUILD
+  libs/lib_b/sub/BUILD
+  libs/other/BUILD
+  $ cat libs/lib_b/BUILD
+  contents
+  more
+
+  $ josh-filter ':regex="lib_("'
+  ERROR: invalid regex "lib_("
+   --> 1:1
+    |
+  1 | :regex="lib_("
+    | ^------------^
+  [1]
@@ -0,0 +1,57 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p sub1/sub2
+  $ echo "url = https://build\.internal.example.com/job" > sub1/config.txt
+  $ echo "host = build.internal.example.com" > sub1/sub2/config.txt
+  $ echo "host = build.internal.example.com" > sub1/sub2/config.bin
+  $ echo "Copyright ACME Internal, 2020" > sub1/file1
+  $ git add sub1
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter -p ':replace="**/*.txt","build\.internal\.example\.com","ci.example.com"'
+  :replace="**/*.txt","build\.internal\.example\.com","ci.example.com"
+
+  $ josh-filter -s ':/sub1:replace="**/*.txt","build\.internal\.example\.com","ci.example.com"' master --update refs/heads/filtered
+  [1] :/sub1
+  [1] :replace="**/*.txt","build\.internal\.example\.com","ci.example.com"
+  $ git checkout -q filtered
+  $ cat config.txt
+  url = https://build\.internal.example.com/job
+  $ cat sub2/config.txt
+  host = ci.example.com
+  $ cat sub2/config.bin
+  host = build.internal.example.com
+
+  $ echo "port = 80" >> sub2/config.txt
+  $ echo "mirror = ci.example.com" > sub2/new.txt
+  $ git add .
+  $ git commit -m "edit files" 1> /dev/null
+  $ josh-filter ':/sub1:replace="**/*.txt","build\.internal\.example\.com","ci.example.com"' --reverse master --update refs/heads/filtered
+  $ git show master:sub1/sub2/config.txt
+  host = build.internal.example.com
+  port = 80
+  $ git show master:sub1/sub2/new.txt
+  mirror = build.internal.example.com
+  $ git show master:sub1/config.txt
+  url = https://build\.internal.example.com/job
+
+  $ josh-filter ':/sub1:replace="*","(?i)acme internal, (\d+)","ACME, $1"' master --update refs/heads/license
+  $ git show license:file1
+  Copyright ACME, 2020
+  $ git checkout -q license
+  $ echo "More text" >> config.txt
+  $ git add .
+  $ git commit -m "edit config" 1> /dev/null
+  $ josh-filter ':/sub1:replace="*","(?i)acme internal, (\d+)","ACME, $1"' --reverse master --update refs/heads/license
+  $ git show master:sub1/file1
+  Copyright ACME Internal, 2020
+  $ echo "Copyright ACME Internal, 2021" > file1
+  $ git add .
+  $ git commit -m "edit license" 1> /dev/null
+  $ josh-filter ':/sub1:replace="*","(?i)acme internal, (\d+)","ACME, $1"' --reverse master --update refs/heads/license
+  ERROR: JoshError("\nCan't apply \"edit license\" (*)\nfilter not reversible: file1") (glob)
+  [1]
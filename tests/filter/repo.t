@@ -0,0 +1,79 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init other 1> /dev/null
+  $ cd other
+  $ mkdir -p lib doc
+  $ echo contents > lib/a
+  $ echo contents > doc/file
+  $ git add .
+  $ GIT_COMMITTER_DATE="2005-04-07T22:13:10" git commit -m "add lib/a" 1> /dev/null
+  $ echo contents > lib/b
+  $ git add .
+  $ GIT_COMMITTER_DATE="2005-04-07T22:13:30" git commit -m "add lib/b" 1> /dev/null
+
+  $ cd ${TESTTMP}
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+  $ mkdir app
+  $ echo contents > app/file
+  $ git add .
+  $ GIT_COMMITTER_DATE="2005-04-07T22:13:20" git commit -m "add app" 1> /dev/null
+  $ echo more >> app/file
+  $ GIT_COMMITTER_DATE="2005-04-07T22:13:40" git commit -am "edit app" 1> /dev/null
+
+  $ git fetch -q ../other master:refs/josh/upstream/other.git/refs/heads/master
+
+  $ josh-filter -p ':repo=other.git@refs/heads/master:/lib'
+  :repo=other.git@refs/heads/master:/lib
+  $ josh-filter -p ':repo=other.git'
+  ERROR: expected :repo=<repo>@<ref>, got "other.git"
+   --> 1:1
+    |
+  1 | :repo=other.git
+    | ^-------------^
+  [1]
+
+Each commit gets the latest content of the other repository that is not newer than
+the commit itself, the history of the other repository is merged in
+
+  $ josh-filter ':[::app/,lib=:repo=other.git@refs/heads/master:/lib]' master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  *   edit app
+  |\  
+  | * add lib/b
+  * | add app
+  |/  
+  * add lib/a
+  $ git ls-tree -r --name-only filtered~1
+  app/file
+  lib/a
+  $ git ls-tree -r --name-only filtered
+  app/file
+  lib/a
+  lib/b
+
+Pushes can only change paths of the main repository
+
+  $ git clone -q -b filtered . ${TESTTMP}/view
+  $ cd ${TESTTMP}/view
+  $ echo even more >> app/file
+  $ git commit -qam "edit app in view"
+  $ cd ${TESTTMP}/real_repo
+  $ git fetch -q ../view filtered:filtered
+  $ josh-filter ':[::app/,lib=:repo=other.git@refs/heads/master:/lib]' --reverse master --update refs/heads/filtered
+  $ git log --pretty=%s master
+  edit app in view
+  edit app
+  add app
+  $ git ls-tree -r --name-only master
+  app/file
+
+  $ cd ${TESTTMP}/view
+  $ echo more >> lib/a
+  $ git commit -qam "edit lib in view"
+  $ cd ${TESTTMP}/real_repo
+  $ git fetch -q ../view filtered:filtered
+  $ josh-filter ':[::app/,lib=:repo=other.git@refs/heads/master:/lib]' --reverse master --update refs/heads/filtered
+  ERROR: JoshError("\nCan't apply \"edit lib in view\" (f368e515c28484b3a2548e3d0c02800fbcd88bec)\ncan't push changes to \"lib\": it contains content of other.git")
+  [1]
@@ -0,0 +1,55 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ echo contents1 > sub2/file2
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ josh-filter :/sub1 master --update refs/heads/filtered
+
+Root commits pushed without :SQUASH are applied like any other commit
+
+  $ git checkout -q --orphan unrelated
+  $ git rm -q -r -f .
+  $ echo contents2 > file3
+  $ git add file3
+  $ git commit -m "add file3" 1> /dev/null
+  $ git checkout -q filtered
+  $ git reset -q --hard unrelated
+  $ josh-filter :/sub1 --reverse master --update refs/heads/filtered
+
+  $ git log --graph --pretty=%s master
+  * add file3
+  $ git ls-tree -r --name-only master
+  sub1/file3
+
+Also when the root commit is the filtered version of an upstream commit that is not
+part of the filtered history
+
+  $ git checkout -q --orphan other
+  $ git rm -q -r -f .
+  $ mkdir sub1
+  $ echo contents4 > sub1/file4
+  $ git add .
+  $ git commit -m "add file4" 1> /dev/null
+  $ git checkout -q master
+  $ git merge -q --allow-unrelated-histories other -m "merge other"
+
+  $ josh-filter :/sub1:linear master --update refs/heads/linear
+  $ josh-filter :/sub1:linear other --update refs/heads/linear_other
+  $ git log --graph --pretty=%s linear
+  * merge other
+  * add file3
+  $ git checkout -q linear
+  $ git reset -q --hard linear_other
+  $ josh-filter :/sub1:linear --reverse master --update refs/heads/linear
+
+  $ git log --graph --pretty=%s master
+  * add file4
+  $ git ls-tree -r --name-only master
+  sub1/file4
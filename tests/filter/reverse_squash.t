@@ -0,0 +1,46 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ echo contents1 > sub2/file2
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+  $ echo contents2 > sub1/file1
+  $ git commit -a -m "edit file1" 1> /dev/null
+  $ git tag released
+
+  $ josh-filter --squash :/sub1 master --update refs/heads/squashed
+  $ git log --pretty=%s squashed
+  edit file1
+
+Upstream moves on after the snapshot was published
+
+  $ echo contents3 > sub2/file2
+  $ git commit -a -m "edit file2" 1> /dev/null
+  $ git tag upstream
+
+Commits on top of the squashed view are applied to the commit it was taken from
+
+  $ git checkout -q squashed
+  $ echo contents1 > file3
+  $ git add file3
+  $ git commit -m "add file3" 1> /dev/null
+  $ echo contents4 > file1
+  $ git commit -a -m "edit file1 again" 1> /dev/null
+  $ josh-filter --squash :/sub1 --reverse master --update refs/heads/squashed
+
+  $ git log --graph --pretty=%s master upstream
+  * edit file1 again
+  * add file3
+  | * edit file2
+  |/  
+  * edit file1
+  * add files
+  $ git diff --stat released master
+   sub1/file1 | 2 +-
+   sub1/file3 | 1 +
+   2 files changed, 2 insertions(+), 1 deletion(-)
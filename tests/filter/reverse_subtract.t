@@ -0,0 +1,62 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init -q real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p sub1/private sub2
+  $ echo contents1 > sub1/file1
+  $ echo contents1 > sub1/private/file2
+  $ echo contents1 > sub2/file3
+  $ git add .
+  $ git commit -m "initial" 1> /dev/null
+
+  $ FILTER=':subtract[:/sub1,::sub1/private/]:prefix=x'
+  $ josh-filter $FILTER master --update refs/heads/filtered
+  $ git checkout -q filtered
+  $ tree
+  .
+  `-- x
+      `-- file1
+  
+  1 directory, 1 file
+
+Changes are applied and the removed part is kept
+
+  $ echo contents2 > x/file1
+  $ echo contents2 > x/file4
+  $ git add x
+  $ git commit -m "edit filtered" 1> /dev/null
+  $ josh-filter $FILTER --reverse master --update refs/heads/filtered
+
+  $ git checkout -q master
+  $ tree
+  .
+  |-- sub1
+  |   |-- file1
+  |   |-- file4
+  |   `-- private
+  |       `-- file2
+  `-- sub2
+      `-- file3
+  
+  3 directories, 4 files
+  $ cat sub1/file1
+  contents2
+  $ git log --pretty=%s
+  edit filtered
+  initial
+
+Files added to the removed part can not be pushed
+
+  $ git checkout -q filtered
+  $ mkdir x/private
+  $ echo contents3 > x/private/file5
+  $ git add x
+  $ git commit -m "add private file" 1> /dev/null
+  $ josh-filter $FILTER --reverse master --update refs/heads/filtered
+  ERROR: JoshError("\nCan't apply \"add private file\" (b85aea7897ffb10ca6ef4ba3a4f04b21cc8c04ff)\nfilter not reversible: changes to paths removed by :subtract")
+  [1]
+  $ git log --pretty=%s master
+  edit filtered
+  initial
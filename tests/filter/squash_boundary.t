@@ -0,0 +1,107 @@
+  $ git init -q 1> /dev/null
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ git add sub1
+  $ GIT_COMMITTER_DATE="2020-01-01T10:00:00" git commit -m "add file1" 1> /dev/null
+  $ echo contents1 > sub2/file2
+  $ git add sub2
+  $ GIT_COMMITTER_DATE="2020-02-01T10:00:00" git commit -m "add file2" 1> /dev/null
+  $ git tag released
+  $ echo contents2 > sub1/file1
+  $ GIT_COMMITTER_DATE="2020-03-01T10:00:00" git commit -a -m "edit file1" 1> /dev/null
+  $ git checkout -q -b branch
+  $ echo contents3 > sub2/file3
+  $ git add sub2
+  $ GIT_COMMITTER_DATE="2020-04-01T10:00:00" git commit -m "add file3" 1> /dev/null
+  $ git checkout -q master
+  $ echo contents3 > sub1/file1
+  $ GIT_COMMITTER_DATE="2020-04-02T10:00:00" git commit -a -m "edit file1 again" 1> /dev/null
+  $ GIT_COMMITTER_DATE="2020-05-01T10:00:00" git merge -q --no-ff branch -m "merge branch" 1> /dev/null
+
+History up to a commit is squashed into the root
+
+  $ josh-filter ":SQUASH=$(git rev-parse released)" master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  *   merge branch
+  |\  
+  | * add file3
+  * | edit file1 again
+  |/  
+  * edit file1
+  * add file2
+  $ git ls-tree -r --name-only filtered~2
+  sub1/file1
+  sub2/file2
+  $ git diff --stat master filtered
+
+History before a date
+
+  $ josh-filter ":SQUASH=2020-03-15" master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  *   merge branch
+  |\  
+  | * add file3
+  * | edit file1 again
+  |/  
+  * edit file1
+Commits before the boundary that are parents of later commits each become a root
+
+  $ josh-filter ':SQUASH="2020-04-01T12:00:00Z"' master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  *   merge branch
+  |\  
+  | * add file3
+  * edit file1 again
+  * edit file1
+  $ josh-filter -p ':SQUASH="2020-03-15T00:00:00+00:00"'
+  :SQUASH=2020-03-15
+  $ josh-filter -p ':SQUASH="2020-03-15T10:30:00+02:00"'
+  :SQUASH="2020-03-15T08:30:00Z"
+
+The boundary can be combined with other filters
+
+  $ josh-filter ":SQUASH=$(git rev-parse released):/sub1" master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  *   merge branch
+  |\  
+  * | edit file1 again
+  |/  
+  * edit file1
+  * add file2
+
+  $ josh-filter ":SQUASH=released"
+  ERROR: expected a commit sha or a date in :SQUASH=, got "released"
+   --> 1:1
+    |
+  1 | :SQUASH=released
+    | ^--------------^
+  [1]
+
+Commit times are not required to increase, each commit is checked on its own.
+An older commit on top of kept ones becomes a root if a kept commit is based on it
+
+  $ echo contents4 > sub1/file1
+  $ GIT_COMMITTER_DATE="2020-03-20T10:00:00" git commit -a -m "backdated" 1> /dev/null
+  $ echo contents5 > sub1/file1
+  $ GIT_COMMITTER_DATE="2020-06-01T10:00:00" git commit -a -m "after backdated" 1> /dev/null
+  $ josh-filter ':SQUASH="2020-04-01T12:00:00Z"' master --update refs/heads/filtered
+  $ git log --graph --pretty=%s filtered
+  * after backdated
+  * backdated
+  $ git ls-tree -r --name-only filtered~1
+  sub1/file1
+  sub2/file2
+  sub2/file3
+
+Only the commits the kept history starts on are squashed
+
+  $ cd ${TESTTMP}
+  $ git init -q linear 1> /dev/null
+  $ cd linear
+  $ for i in 1 2 3 4; do echo $i > file; git add file; GIT_COMMITTER_DATE="2020-0$i-01T10:00:00" git commit -q -m "commit $i"; done
+  $ josh-filter ":SQUASH=2020-03-15" master --update refs/heads/filtered
+  $ git log --pretty=%s filtered
+  commit 4
+  commit 3
+  $ git fsck --unreachable --no-reflogs 2>/dev/null
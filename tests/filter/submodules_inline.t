@@ -0,0 +1,91 @@
+  $ export TESTTMP=${PWD}
+
+  $ cd ${TESTTMP}
+  $ git init libs 1> /dev/null
+  $ cd libs
+
+  $ mkdir sub1
+  $ echo contents1 > sub1/file1
+  $ git add sub1
+  $ git commit -m "add file1" 1> /dev/null
+
+  $ cd ${TESTTMP}
+  $ git init app 1> /dev/null
+  $ cd app
+  $ echo contents > app_file
+  $ git add app_file
+  $ git commit -m "init" 1> /dev/null
+  $ git submodule add ../libs 2> /dev/null
+  $ git commit -m "add libs" 1> /dev/null
+
+Submodules are only inlined when the commit is available
+
+  $ git clone -q . ${TESTTMP}/app_clone
+  $ cd ${TESTTMP}/app_clone
+  $ josh-filter -s :submodules master --update refs/heads/filtered
+  [1] :submodules
+  $ git ls-tree -r --name-only filtered
+  .gitmodules
+  app_file
+  libs
+
+Results are not cached while the submodule commit is missing, so fetching it
+later updates them
+
+  $ git fetch -q ${TESTTMP}/libs master
+  $ josh-filter :submodules master --update refs/heads/filtered
+  $ git ls-tree -r --name-only filtered
+  .gitmodules
+  app_file
+  libs/sub1/file1
+
+  $ cd ${TESTTMP}/app
+  $ git fetch -q ../libs master
+  $ josh-filter -s :submodules master --update refs/heads/filtered
+  [2] :submodules
+  $ git ls-tree -r --name-only filtered
+  .gitmodules
+  app_file
+  libs/sub1/file1
+
+  $ josh-filter -p ':submodules:/libs'
+  :submodules:/libs
+  $ josh-filter ':submodules:/libs' master --update refs/heads/libs
+  $ git ls-tree -r --name-only libs
+  sub1/file1
+
+Pushing changes to the submodule creates a commit in the submodule
+
+  $ git clone -q -b filtered . ${TESTTMP}/view
+  $ cd ${TESTTMP}/view
+  $ echo contents2 > libs/sub1/file2
+  $ echo more >> app_file
+  $ git add .
+  $ git commit -m "edit app and libs" 1> /dev/null
+  $ cd ${TESTTMP}/app
+  $ git fetch -q ../view filtered:filtered
+  $ josh-filter :submodules --reverse master --update refs/heads/filtered
+  $ git log --pretty=%s master
+  edit app and libs
+  add libs
+  init
+  $ git diff --name-only master~1 master
+  app_file
+  libs
+  $ git log --pretty=%s $(git rev-parse master:libs)
+  edit app and libs
+  add file1
+  $ git ls-tree -r --name-only $(git rev-parse master:libs)
+  sub1/file1
+  sub1/file2
+
+Unchanged submodules keep their commit
+
+  $ cd ${TESTTMP}/view
+  $ echo even more >> app_file
+  $ git commit -am "edit app" 1> /dev/null
+  $ cd ${TESTTMP}/app
+  $ git fetch -q ../view filtered:filtered
+  $ josh-filter :submodules --reverse master --update refs/heads/filtered
+  $ git diff --name-only master~1 master
+  app_file
@@ -18,7 +18,11 @@
   * add files
 
   $ josh-filter -s :nosuch=filter master --update refs/josh/filtered
-  ERROR: JoshError("invalid filter")
+  ERROR: invalid filter
+   --> 1:1
+    |
+  1 | :nosuch=filter
+    | ^------------^
   [1]
 
   $ git ls-tree --name-only -r refs/josh/filtered
@@ -0,0 +1,70 @@
+  $ export TERM=dumb
+  $ export RUST_LOG_STYLE=never
+
+  $ git init real_repo 1> /dev/null
+  $ cd real_repo
+
+  $ mkdir -p libs/a libs/b tools other
+  $ echo contents > libs/a/file
+  $ echo contents > libs/b/file
+  $ echo contents > libs/b/test
+  $ echo contents > tools/tool
+  $ echo contents > other/file
+  $ echo contents > other/test
+  $ git add .
+  $ git commit -m "add files" 1> /dev/null
+
+  $ mkdir ws
+  $ cat > ws/workspace.josh <<'EOF'
+  > $notest = :exclude[::test]
+  > $libs = :[
+  >     a = :/libs/a
+  >     b = :/libs/b$notest
+  > ]
+  > deps = $libs
+  > tools = :/tools$notest
+  > EOF
+  $ git add ws
+  $ git commit -m "add ws" 1> /dev/null
+
+  $ josh-filter :workspace=ws master --update refs/heads/filtered
+  $ git ls-tree -r --name-only filtered
+  deps/a/file
+  deps/b/file
+  tools/tool
+  workspace.josh
+
+  $ git checkout -q filtered
+  $ cat >> workspace.josh <<'EOF'
+  > other = :/other$notest
+  > EOF
+  $ git commit -qam "edit ws"
+
+  $ josh-filter :workspace=ws --reverse master --update refs/heads/filtered
+  $ git checkout -q master
+  $ cat ws/workspace.josh
+  $notest = :exclude[::test]
+  $libs = :/libs:[
+      ::a/
+      b = :/b$notest
+  ]
+  
+  deps = $libs
+  other = :/other$notest
+  tools = :/tools$notest
+
+  $ josh-filter :workspace=ws master --update refs/heads/filtered
+  $ git ls-tree -r --name-only filtered
+  deps/a/file
+  deps/b/file
+  other/file
+  tools/tool
+  workspace.josh
+
+  $ josh-filter -p '$undefined:/a'
+  ERROR: undefined filter $undefined
+   --> 1:1
+    |
+  1 | $undefined:/a
+    | ^--------^
+  [1]
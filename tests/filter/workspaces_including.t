@@ -0,0 +1,28 @@
+  $ git init -q 1> /dev/null
+
+  $ mkdir -p lib1/sub app1 ws1 ws2
+  $ echo contents1 > lib1/file1
+  $ echo contents1 > lib1/sub/file2
+  $ echo contents1 > app1/file1
+  $ cat > ws1/workspace.josh <<EOF
+  > libs/lib1 = :/lib1
+  > EOF
+  $ cat > ws2/workspace.josh <<EOF
+  > lib = :/lib1:exclude[::file1]
+  > app = :/app1
+  > EOF
+  $ echo contents1 > ws2/local
+  $ git add .
+  $ git commit -m "initial" 1> /dev/null
+
+  $ josh-filter --workspaces-including lib1/file1
+  ws1/workspace.josh: libs/lib1/file1
+  $ josh-filter --workspaces-including lib1/sub/file2
+  ws1/workspace.josh: libs/lib1/sub/file2
+  ws2/workspace.josh: lib/sub/file2
+  $ josh-filter --workspaces-including ws2/local
+  ws2/workspace.josh: local
+  $ josh-filter --workspaces-including app1/file1
+  ws2/workspace.josh: app/file1
+  $ josh-filter --workspaces-including app1
+  $ josh-filter --workspaces-including nosuch/file
@@ -27,6 +27,17 @@
   fatal: repository 'http://localhost:8002/xxx/' not found
   [128]
 
+  $ git clone -q http://localhost:8002/real_repo.git:/sub1:[x.git full_repo
+  remote: unexpected "x"
+  remote:  --> 1:9
+  remote:   |
+  remote: 1 | :/sub1:[x
+  remote:   |         ^---
+  remote:   |
+  remote:   = expected filter
+  fatal: unable to access 'http://localhost:8002/real_repo.git:/sub1:[x.git/': The requested URL returned error: 400
+  [128]
+
 
   $ bash ${TESTDIR}/destroy_test_env.sh
   refs
@@ -0,0 +1,87 @@
+  $ EXTRA_OPTS=--notes . ${TESTDIR}/setup_test_env.sh
+  $ cd ${TESTTMP}
+
+  $ git clone -q http://localhost:8001/real_repo.git
+  warning: You appear to have cloned an empty repository.
+
+  $ cd real_repo
+
+  $ mkdir sub1 sub2
+  $ echo contents1 > sub1/file1
+  $ git add sub1
+  $ git commit -q -m "add file1"
+  $ echo contents2 > sub2/file2
+  $ git add sub2
+  $ git commit -q -m "add file2"
+  $ echo contents3 > sub1/file3
+  $ git add sub1
+  $ git commit -q -m "add file3"
+  $ git notes add -m "note on file1" HEAD~2
+  $ git notes add -m "note on file2" HEAD~1
+
+  $ git push -q origin master refs/notes/commits
+
+  $ cd ${TESTTMP}
+
+  $ git clone -q http://localhost:8002/real_repo.git:/sub1.git sub1
+  $ cd sub1
+
+Notes are fetched like other refs and attached to the filtered commits
+
+  $ git fetch -q origin "refs/notes/*:refs/notes/*"
+  $ git log --pretty="%s: %N"
+  add file3: 
+  add file1: note on file1
+  
+
+Pushed notes are attached to the original commits
+
+  $ git notes add -m "note on file3" HEAD
+  $ git notes append -m "appended" HEAD~1
+  $ git push -q origin refs/notes/commits
+  remote: josh-proxy        
+  remote: response from upstream:        
+  remote: To http://localhost:8001/real_repo.git        
+  remote:    98632d5..bcd3a8d  JOSH_PUSH -> refs/notes/commits        
+  remote: 
+  remote: 
+
+  $ cd ${TESTTMP}/real_repo
+  $ git fetch -q origin "+refs/notes/*:refs/notes/*"
+  $ git log --pretty="%s: %N"
+  add file3: note on file3
+  
+  add file2: note on file2
+  
+  add file1: note on file1
+  
+  appended
+  
+
+  $ bash ${TESTDIR}/destroy_test_env.sh
+  "real_repo.git" = [
+      ':/sub1',
+      ':/sub2',
+  ]
+  refs
+  |-- heads
+  |-- josh
+  |   |-- filtered
+  |   |   `-- real_repo.git
+  |   |       |-- %3A%2Fsub1
+  |   |       |   `-- heads
+  |   |       |       `-- master
+  |   |       `-- %3A%2Fsub2
+  |   |           `-- heads
+  |   |               `-- master
+  |   `-- upstream
+  |       `-- real_repo.git
+  |           `-- refs
+  |               |-- heads
+  |               |   `-- master
+  |               `-- notes
+  |                   `-- commits
+  |-- namespaces
+  `-- tags
+  
+  15 directories, 4 files
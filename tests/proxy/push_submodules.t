@@ -0,0 +1,114 @@
+  $ . ${TESTDIR}/setup_test_env.sh
+
+  $ cd ${TESTTMP}
+  $ git clone -q http://localhost:8001/real/repo2.git 1> /dev/null
+  warning: You appear to have cloned an empty repository.
+  $ cd repo2
+  $ echo contents1 > file1
+  $ git add file1
+  $ git commit -m "add file1" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real/repo2.git
+   * [new branch]      master -> master
+
+  $ cd ${TESTTMP}
+  $ git clone -q http://localhost:8001/real_repo.git 1> /dev/null
+  warning: You appear to have cloned an empty repository.
+  $ cd real_repo
+  $ git submodule -q add ../real/repo2.git sub
+  $ git commit -m "add submodule" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real_repo.git
+   * [new branch]      master -> master
+
+Fetch the submodule through the proxy, so its commits are available for inlining
+  $ cd ${TESTTMP}
+  $ git clone -q http://localhost:8002/real/repo2.git repo2_proxy
+
+  $ git clone -q http://localhost:8002/real_repo.git:submodules.git inlined
+  $ cd inlined
+  $ tree
+  .
+  `-- sub
+      `-- file1
+  
+  1 directory, 1 file
+
+  $ echo contents2 > sub/file1
+  $ git commit -a -m "change inlined submodule" 1> /dev/null
+  $ git push 2>&1 >/dev/null | grep -v "^remote: $"
+  remote: josh-proxy        
+  remote: response from upstream:        
+  remote: To http://localhost:8001/real/repo2.git        
+  remote:    0b4cf6c..03bcab8  JOSH_PUSH -> master        
+  remote: To http://localhost:8001/real_repo.git        
+  remote:    256432a..6cfc2f7  JOSH_PUSH -> master        
+  To http://localhost:8002/real_repo.git:submodules.git
+     f395462..a8c91ab  master -> master
+
+The new submodule commit was pushed before the superproject referencing it
+  $ cd ${TESTTMP}/repo2
+  $ git pull -q
+  $ git log --oneline --format="%s"
+  change inlined submodule
+  add file1
+  $ cat file1
+  contents2
+
+  $ cd ${TESTTMP}/real_repo
+  $ git pull -q
+  $ git log --oneline --format="%s"
+  change inlined submodule
+  add submodule
+  $ test "$(git rev-parse HEAD:sub)" = "$(git -C ${TESTTMP}/repo2 rev-parse HEAD)"
+
+Submodules not on the upstream are not pushed to, so the push is rejected
+  $ git config -f .gitmodules submodule.sub.url http://localhost:8003/other.git
+  $ git commit -a -m "move submodule" 1> /dev/null
+  $ git push -q 1> /dev/null
+
+  $ cd ${TESTTMP}/inlined
+  $ git pull -q --rebase
+  $ echo contents3 > sub/file1
+  $ git commit -a -m "change moved submodule" 1> /dev/null
+  $ git push 2>&1 >/dev/null | grep -v "^remote: $"
+  remote: josh-proxy        
+  remote: response from upstream:        
+  remote: submodule url "http://localhost:8003/other.git" is not on the upstream        
+  remote: error: hook declined to update refs/heads/master        
+  To http://localhost:8002/real_repo.git:submodules.git
+   ! [remote rejected] master -> master (hook declined)
+  error: failed to push some refs to 'http://localhost:8002/real_repo.git:submodules.git'
+
+  $ cd ${TESTTMP}/repo2
+  $ git pull -q
+  $ git log --oneline --format="%s"
+  change inlined submodule
+  add file1
+
+  $ bash ${TESTDIR}/destroy_test_env.sh
+  refs
+  |-- heads
+  |-- josh
+  |   |-- filtered
+  |   |   |-- real%2Frepo2.git
+  |   |   |   `-- %3A
+  |   |   |       `-- heads
+  |   |   |           `-- master
+  |   |   `-- real_repo.git
+  |   |       `-- %3Asubmodules
+  |   |           `-- heads
+  |   |               `-- master
+  |   `-- upstream
+  |       |-- real%2Frepo2.git
+  |       |   `-- refs
+  |       |       `-- heads
+  |       |           `-- master
+  |       `-- real_repo.git
+  |           `-- refs
+  |               `-- heads
+  |                   `-- master
+  |-- namespaces
+  `-- tags
+  
+  18 directories, 4 files
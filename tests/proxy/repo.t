@@ -0,0 +1,104 @@
+  $ . ${TESTDIR}/setup_test_env.sh
+
+  $ cd ${TESTTMP}
+  $ git clone -q http://localhost:8001/real/repo2.git 1> /dev/null
+  warning: You appear to have cloned an empty repository.
+  $ cd repo2
+  $ mkdir lib
+  $ echo contents > lib/a
+  $ git add lib
+  $ git commit -m "add lib/a" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real/repo2.git
+   * [new branch]      master -> master
+
+  $ cd ${TESTTMP}
+  $ git clone -q http://localhost:8001/real_repo.git 1> /dev/null
+  warning: You appear to have cloned an empty repository.
+  $ cd real_repo
+  $ mkdir app
+  $ echo contents > app/file
+  $ git add app
+  $ git commit -m "add app" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real_repo.git
+   * [new branch]      master -> master
+
+Other repositories that can be read are fetched and used
+  $ cd ${TESTTMP}
+  $ git clone -q "http://localhost:8002/real_repo.git:[::app/,lib=:repo=real/repo2.git@refs/heads/master:/lib].git" combined
+  $ cd combined
+  $ tree
+  .
+  |-- app
+  |   `-- file
+  `-- lib
+      `-- a
+  
+  2 directories, 2 files
+
+Repositories that can't be read are rejected, also when they are used in a workspace file
+  $ cd ${TESTTMP}
+  $ git clone -q "http://localhost:8002/real_repo.git:[::app/,lib=:repo=nosuch.git@refs/heads/master].git" nosuch
+  remote: no access to "nosuch.git" used by :repo=
+  fatal: unable to access 'http://localhost:8002/real_repo.git:[::app/,lib=:repo=nosuch.git@refs/heads/master].git/': The requested URL returned error: 403
+  [128]
+
+  $ cd ${TESTTMP}/real_repo
+  $ mkdir ws
+  $ cat > ws/workspace.josh <<EOF
+  > lib = :repo=nosuch.git@refs/heads/master
+  > EOF
+  $ git add ws
+  $ git commit -m "add workspace" 1> /dev/null
+  $ git push 1> /dev/null
+  To http://localhost:8001/real_repo.git
+     d95690a..bb1625b  master -> master
+
+  $ cd ${TESTTMP}
+  $ git clone -q "http://localhost:8002/real_repo.git:workspace=ws.git" ws
+  remote: no access to "nosuch.git" used by :repo=
+  fatal: unable to access 'http://localhost:8002/real_repo.git:workspace=ws.git/': The requested URL returned error: 403
+  [128]
+
+  $ bash ${TESTDIR}/destroy_test_env.sh
+  "real/repo2.git" = [':/lib']
+  "real_repo.git" = [
+      ':/app',
+      ':/ws',
+      ':workspace=ws',
+  ]
+  refs
+  |-- heads
+  |-- josh
+  |   |-- filtered
+  |   |   |-- real%2Frepo2.git
+  |   |   |   `-- %3A%2Flib
+  |   |   |       `-- heads
+  |   |   |           `-- master
+  |   |   `-- real_repo.git
+  |   |       |-- %3A%2Fapp
+  |   |       |   `-- heads
+  |   |       |       `-- master
+  |   |       |-- %3A%2Fws
+  |   |       |   `-- heads
+  |   |       |       `-- master
+  |   |       |-- %3A%5B%3A%3Aapp%2F,%3Arepo=real%2Frepo2.git%40refs%2Fheads%2Fmaster%3A%2Flib%3Aprefix=lib%5D
+  |   |       |   `-- heads
+  |   |       |       `-- master
+  |   |       `-- %3Aworkspace=ws
+  |   |           `-- heads
+  |   |               `-- master
+  |   `-- upstream
+  |       |-- real%2Frepo2.git
+  |       |   `-- refs
+  |       |       `-- heads
+  |       |           `-- master
+  |       `-- real_repo.git
+  |           `-- refs
+  |               `-- heads
+  |                   `-- master
+  |-- namespaces
+  `-- tags
+  
+  24 directories, 7 files
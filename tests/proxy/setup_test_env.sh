@@ -26,6 +26,7 @@ ${TESTDIR}/../../target/debug/josh-proxy\
     --graphql-root\
     --local=${TESTTMP}/remote/scratch/\
     --remote=http://localhost:8001\
+    ${EXTRA_OPTS}\
     > ${TESTTMP}/josh-proxy.out 2>&1 &
 echo $! > ${TESTTMP}/proxy_pid
 
@@ -290,7 +290,7 @@
   |   |               `-- master
   |   |-- rewrites
   |   |   `-- real_repo.git
-  |   |       `-- 7bd92d97e96693ea7fd7eb5757b3580002889948
+  |   |       `-- 9eeff928673d9a504433669afbcc2a4d8811e0d5
   |   |           `-- r_fa3b9622c1bcc8363c27d4eb05d1ae8dae15e871
   |   `-- upstream
   |       `-- real_repo.git
@@ -213,7 +213,7 @@
   |   |               `-- master
   |   |-- rewrites
   |   |   `-- real_repo.git
-  |   |       `-- 7bd92d97e96693ea7fd7eb5757b3580002889948
+  |   |       `-- 9eeff928673d9a504433669afbcc2a4d8811e0d5
   |   |           |-- r_9a28fa82a736714d831348bbf62b951be65331b7
   |   |           `-- r_9bd58f891b4f17736c1b51903837de717fce13a5
   |   `-- upstream
@@ -62,12 +62,13 @@ Error: comment in the middle
   remote: Can't apply "add workspace file" (4f70c9a0179b1cae80148572c8dfc3ba1f2d43a2)        
   remote: Invalid workspace:        
   remote: ----        
+  remote: unexpected "# comment 2"        
   remote:  --> 6:1        
   remote:   |        
   remote: 6 | # comment 2        
   remote:   | ^---        
   remote:   |        
-  remote:   = expected EOI, filter_spec, or dst_path        
+  remote:   = expected end of input, filter or path        
   remote: 
   remote: # comment        
   remote: #        
@@ -101,12 +102,13 @@ Error in filter
   remote: Can't apply "add workspace file" (74128cac082e518bc3ddec183bb11b16856406cd)        
   remote: Invalid workspace:        
   remote: ----        
+  remote: unexpected "/sub2"        
   remote:  --> 1:9        
   remote:   |        
   remote: 1 | a/b = :b/sub2        
   remote:   |         ^---        
   remote:   |        
-  remote:   = expected EOI, filter_group, filter_subdir, filter_nop, filter_presub, filter, or filter_noarg        
+  remote:   = expected end of input or filter        
   remote: 
   remote: a/b = :b/sub2        
   remote: c = :/sub1        
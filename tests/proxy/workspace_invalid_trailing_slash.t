@@ -134,12 +134,13 @@ Flushed credential cache
   remote: Can't apply "mod workspace" (4e531443c5533e6d1b2503d0fad238cfc8491807)
   remote: Invalid workspace:
   remote: ----
+  remote: unexpected "a/ = :/sub1"
   remote:  --> 1:1
   remote:   |
   remote: 1 | a/ = :/sub1
   remote:   | ^---
   remote:   |
-  remote:   = expected workspace_file
+  remote:   = expected workspace entry
   remote:
   remote: a/ = :/sub1
   remote:
@@ -207,7 +208,7 @@ Flushed credential cache
   |   |               `-- master
   |   |-- rewrites
   |   |   `-- real_repo.git
-  |   |       `-- 7bd92d97e96693ea7fd7eb5757b3580002889948
+  |   |       `-- 9eeff928673d9a504433669afbcc2a4d8811e0d5
   |   |           `-- r_c255706f564f629eed1756b789d761048cfe060a
   |   `-- upstream
   |       `-- real_repo.git
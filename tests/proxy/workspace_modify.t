@@ -517,7 +517,7 @@ Note that ws/d/ is now present in the ws
   |   |               `-- master
   |   |-- rewrites
   |   |   `-- real_repo.git
-  |   |       `-- 7bd92d97e96693ea7fd7eb5757b3580002889948
+  |   |       `-- 9eeff928673d9a504433669afbcc2a4d8811e0d5
   |   |           |-- r_1a909d6e8ba43c6eaf211ef04440984d38bc26e6
   |   |           |-- r_3136fff7280627623bf4d71191d1aea783579be0
   |   |           `-- r_4a199f3a19a292e6639dede0f8602afc19a82dfc
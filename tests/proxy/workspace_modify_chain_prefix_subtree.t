@@ -389,7 +389,7 @@ Note that ws/d/ is now present in the ws
   |   |               `-- master
   |   |-- rewrites
   |   |   `-- real_repo.git
-  |   |       `-- 5c1a6d0fe36ae7aa1727c8f071ae92d3645a63ea
+  |   |       `-- a00a9e7bdb739d80bcf12940e81d7f54636d4788
   |   |           |-- r_44edc62d506b9805a3edfc74db15b1cc0bfc6871
   |   |           `-- r_9d72b88b11aed97d3313f0a6d80894ee2ffdf3e9
   |   `-- upstream